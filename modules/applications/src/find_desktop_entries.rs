@@ -1,16 +1,26 @@
 //! Desktop file enumeration mechanism from [anyrun](https://github.com/anyrun-org/anyrun)
 //! (scrubber.rs)
 
-use log::error;
-use std::{collections::HashMap, env, ffi::OsStr, fs, path::PathBuf};
+use log::{error, warn};
+use std::{
+    collections::HashMap,
+    env,
+    ffi::OsStr,
+    fs,
+    path::{Path, PathBuf},
+};
+use walkdir::WalkDir;
 
 #[allow(dead_code)]
 #[derive(Clone, Debug)]
 pub struct DesktopEntry {
+    /// desktop file id, i.e. the path relative to the applications dir with '/' replaced by '-'
+    pub id: String,
     pub exec: String,
     pub path: Option<PathBuf>,
     pub name: String,
     pub keywords: Vec<String>,
+    pub categories: Vec<String>,
     pub desc: Option<String>,
     pub icon: String,
     pub term: bool,
@@ -22,9 +32,9 @@ const FIELD_CODE_LIST: &[&str] = &[
 ];
 
 impl DesktopEntry {
-    fn from_dir_entry(entry: &fs::DirEntry, desktop_actions: bool) -> Vec<Self> {
-        if entry.path().extension() == Some(OsStr::new("desktop")) {
-            let content = match fs::read_to_string(entry.path()) {
+    fn from_path(path: &Path, id: &str, desktop_actions: bool) -> Vec<Self> {
+        if path.extension() == Some(OsStr::new("desktop")) {
+            let content = match fs::read_to_string(path) {
                 Ok(content) => content,
                 Err(_) => return Vec::new(),
             };
@@ -71,6 +81,7 @@ impl DesktopEntry {
                         }
                     {
                         Some(DesktopEntry {
+                            id: id.to_string(),
                             exec: {
                                 let mut exec = map.get("Exec")?.to_string();
 
@@ -90,6 +101,16 @@ impl DesktopEntry {
                                         .collect::<Vec<_>>()
                                 })
                                 .unwrap_or_default(),
+                            categories: map
+                                .get("Categories")
+                                .map(|categories| {
+                                    categories
+                                        .split(';')
+                                        .filter(|s| !s.is_empty())
+                                        .map(|s| s.to_owned())
+                                        .collect::<Vec<_>>()
+                                })
+                                .unwrap_or_default(),
                             desc: None,
                             icon: map
                                 .get("Icon")
@@ -124,6 +145,7 @@ impl DesktopEntry {
 
                     if section[0].starts_with("[Desktop Action") {
                         ret.push(DesktopEntry {
+                            id: entry.id.clone(),
                             exec: match map.get("Exec") {
                                 Some(exec) => {
                                     let mut exec = exec.to_string();
@@ -149,6 +171,7 @@ impl DesktopEntry {
                                         .collect::<Vec<_>>()
                                 })
                                 .unwrap_or_default(),
+                            categories: entry.categories.clone(),
                             desc: Some(entry.name.clone()),
                             icon: entry.icon.clone(),
                             term: map
@@ -169,6 +192,29 @@ impl DesktopEntry {
     }
 }
 
+/// Recursively collects the desktop files under an applications dir, keyed by desktop file id.
+/// Symlinks are followed, loops and unreadable subdirectories are skipped with a warning.
+fn desktop_files(apps_dir: &Path) -> HashMap<String, PathBuf> {
+    let mut files = HashMap::new();
+    for der in WalkDir::new(apps_dir).follow_links(true) {
+        let de = match der {
+            Ok(de) => de,
+            Err(why) => {
+                warn!("Skipping part of {apps_dir:?}: {why}");
+                continue;
+            }
+        };
+        if !de.file_type().is_file() || de.path().extension() != Some(OsStr::new("desktop")) {
+            continue;
+        }
+        if let Ok(subpath) = de.path().strip_prefix(apps_dir) {
+            let id = subpath.to_string_lossy().replace('/', "-");
+            files.insert(id, de.into_path());
+        }
+    }
+    files
+}
+
 pub fn get_desktop_entries(
     desktop_actions: bool,
 ) -> Result<Vec<DesktopEntry>, Box<dyn std::error::Error>> {
@@ -186,68 +232,45 @@ pub fn get_desktop_entries(
         }
     };
 
-    let mut entries: HashMap<String, DesktopEntry> = match env::var("XDG_DATA_DIRS") {
+    let mut files: HashMap<String, PathBuf> = match env::var("XDG_DATA_DIRS") {
         Ok(data_dirs) => {
-            // The vec for all the DirEntry objects
-            let mut paths = Vec::new();
-            // Parse the XDG_DATA_DIRS variable and list files of all the paths
-            for dir in data_dirs.split(':') {
-                match fs::read_dir(format!("{}/applications/", dir)) {
-                    Ok(dir) => {
-                        paths.extend(dir);
-                    }
-                    Err(why) => {
-                        error!("Error reading directory {}: {}", dir, why);
-                    }
+            let mut files = HashMap::new();
+            // Earlier dirs take precedence, so are inserted last
+            for dir in data_dirs.split(':').rev() {
+                let apps_dir = PathBuf::from(format!("{}/applications/", dir));
+                if apps_dir.is_dir() {
+                    files.extend(desktop_files(&apps_dir));
+                } else {
+                    error!("Error reading directory {}: not a directory", dir);
                 }
             }
-            // Make sure the list of paths isn't empty
-            if paths.is_empty() {
+            // Make sure the list of files isn't empty
+            if files.is_empty() {
                 return Err("No valid desktop file dirs found!".into());
             }
 
-            // Return it
-            paths
+            files
         }
-        Err(_) => fs::read_dir("/usr/share/applications")?.collect(),
-    }
-    .into_iter()
-    .filter_map(|entry| {
-        let entry = match entry {
-            Ok(entry) => entry,
-            Err(_why) => return None,
-        };
-        let entries = DesktopEntry::from_dir_entry(&entry, desktop_actions);
-        Some(
-            entries
-                .into_iter()
-                .map(|entry| (format!("{}{}", entry.name, entry.icon), entry)),
-        )
-    })
-    .flatten()
-    .collect();
+        Err(_) => {
+            let apps_dir = Path::new("/usr/share/applications");
+            // surface the io error if the default dir is unusable
+            fs::read_dir(apps_dir)?;
+            desktop_files(apps_dir)
+        }
+    };
 
     // Go through user directory desktop files for overrides
-    match fs::read_dir(&user_path) {
-        Ok(dir_entries) => entries.extend(
-            dir_entries
-                .into_iter()
-                .filter_map(|entry| {
-                    let entry = match entry {
-                        Ok(entry) => entry,
-                        Err(_why) => return None,
-                    };
-                    let entries = DesktopEntry::from_dir_entry(&entry, desktop_actions);
-                    Some(
-                        entries
-                            .into_iter()
-                            .map(|entry| (format!("{}{}", entry.name, entry.icon), entry)),
-                    )
-                })
-                .flatten(),
-        ),
-        Err(why) => error!("Error reading directory {}: {}", user_path, why),
+    let user_dir = Path::new(&user_path);
+    match user_dir.is_dir() {
+        true => files.extend(desktop_files(user_dir)),
+        false => error!("Error reading directory {}: not a directory", user_path),
     }
 
-    Ok(entries.into_values().collect())
+    // NOTE: sorted by id, so that entry order (and so item handles) is stable between runs
+    let mut files: Vec<_> = files.into_iter().collect();
+    files.sort();
+    Ok(files
+        .into_iter()
+        .flat_map(|(id, path)| DesktopEntry::from_path(&path, &id, desktop_actions))
+        .collect())
 }
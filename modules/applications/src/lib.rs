@@ -1,8 +1,8 @@
-use std::{env, process::Command};
+use std::{env, process::Command, sync::Mutex};
 
 use find_desktop_entries::{get_desktop_entries, DesktopEntry};
 use log::{error, info};
-use searchthing_interface::{FuzzySearch, SearchItemHandle, SearchMethod, SearchModule};
+use searchthing_interface::{MatchMode, SearchIndex, SearchItemHandle, SearchModule};
 
 mod find_desktop_entries;
 
@@ -17,53 +17,176 @@ impl From<DesktopEntry> for WrappedDesktopEntry {
         if let Some(desc) = &entry.desc {
             search_text = format!("{search_text}||{desc}");
         }
-        search_text = search_text.to_lowercase();
         Self { entry, search_text }
     }
 }
 
 static EMPTY: &str = "";
 
+/// Prefix for filtering by category, e.g. "cat:dev firefox".
+static CATEGORY_PREFIX: &str = "cat:";
+
+/// Freedesktop main categories (Audio and Video being implied by AudioVideo), with an icon each
+static MAIN_CATEGORIES: &[(&str, &str)] = &[
+    ("AudioVideo", "applications-multimedia"),
+    ("Development", "applications-development"),
+    ("Education", "applications-education"),
+    ("Game", "applications-games"),
+    ("Graphics", "applications-graphics"),
+    ("Network", "applications-internet"),
+    ("Office", "applications-office"),
+    ("Science", "applications-science"),
+    ("Settings", "preferences-desktop"),
+    ("System", "applications-system"),
+    ("Utility", "applications-utilities"),
+];
+
+/// A main category listed when browsing
+struct CategoryItem {
+    name: &'static str,
+    icon: &'static str,
+    desc: String,
+}
+
 pub struct ApplicationsModule {
     name: String,
     icon: String,
     entries: Vec<WrappedDesktopEntry>,
+    /// of the entries' search texts
+    index: SearchIndex,
+    /// non-empty main categories, their handles follow on from those of the entries
+    categories: Vec<CategoryItem>,
+    match_mode: MatchMode,
+    /// the number of items listed by the last queery if it browsed, the index counting them
+    /// otherwise
+    browsed_count: Mutex<Option<usize>>,
 }
 
 impl Default for ApplicationsModule {
     fn default() -> Self {
-        let entries = get_desktop_entries(true)
+        let entries: Vec<WrappedDesktopEntry> = get_desktop_entries(true)
             .unwrap()
             .into_iter()
             .map(|de| de.into())
             .collect();
+        let categories = MAIN_CATEGORIES
+            .iter()
+            .filter_map(|(name, icon)| {
+                let count = entries
+                    .iter()
+                    .filter(|w| w.entry.desc.is_none() && w.in_category(&name.to_lowercase()))
+                    .count();
+                (count > 0).then(|| CategoryItem {
+                    name,
+                    icon,
+                    desc: format!("{count} applications"),
+                })
+            })
+            .collect();
+        let mut index = SearchIndex::default();
+        for w in &entries {
+            index.push([&w.search_text]);
+        }
         Self {
             entries,
+            index,
+            categories,
             name: "Applications".into(),
             icon: "application-x-executable".into(),
+            match_mode: Default::default(),
+            browsed_count: Default::default(),
         }
     }
 }
 
+impl WrappedDesktopEntry {
+    /// if any of the entry's categories start with the given (lowercase) text
+    fn in_category(&self, category: &str) -> bool {
+        self.entry
+            .categories
+            .iter()
+            .any(|c| c.to_lowercase().starts_with(category))
+    }
+}
+
+impl ApplicationsModule {
+    fn category(&self, item: SearchItemHandle) -> Option<&CategoryItem> {
+        let idx = (item.0 as usize).checked_sub(self.entries.len())?;
+        self.categories.get(idx)
+    }
+
+    /// lists the main categories, for browsing with an empty queery
+    fn browse_categories(&self) -> Vec<SearchItemHandle> {
+        // NOTE: not limited by max_returned, as this is a short fixed list
+        *self.browsed_count.lock().unwrap() = Some(self.categories.len());
+        (0..self.categories.len())
+            .map(|i| SearchItemHandle((self.entries.len() + i) as i32))
+            .collect()
+    }
+
+    /// lists the (main) entries in a category by name, for when no search text follows the filter
+    fn browse_category(&self, category: &str, max_returned: u32) -> Vec<SearchItemHandle> {
+        let mut matches = self
+            .entries
+            .iter()
+            .enumerate()
+            .filter(|(_, w)| w.entry.desc.is_none() && w.in_category(category))
+            .collect::<Vec<_>>();
+        matches.sort_by_key(|(_, w)| w.entry.name.to_lowercase());
+        *self.browsed_count.lock().unwrap() = Some(matches.len());
+        matches
+            .into_iter()
+            .take(max_returned as usize)
+            .map(|(idx, _)| SearchItemHandle(idx as i32))
+            .collect()
+    }
+}
+
+/// splits a queery like "cat:dev firefox" into the (lowercase) category and the search text, an
+/// empty category (e.g. "cat: firefox") being none
+fn split_category(input: &str) -> (Option<String>, &str) {
+    let Some(rest) = input.strip_prefix(CATEGORY_PREFIX) else {
+        return (None, input);
+    };
+    let (category, rest) = rest.split_once(' ').unwrap_or((rest, ""));
+    let category = (!category.is_empty()).then(|| category.to_lowercase());
+    (category, rest.trim_start())
+}
+
+#[test]
+fn split_category_test() {
+    assert_eq!(split_category("firefox"), (None, "firefox"));
+    assert_eq!(
+        split_category("cat:Dev  code"),
+        (Some("dev".into()), "code")
+    );
+    assert_eq!(split_category("cat:game"), (Some("game".into()), ""));
+    assert_eq!(split_category("cat: foo"), (None, "foo"));
+    assert_eq!(split_category("cat:"), (None, ""));
+}
+
 impl SearchModule for ApplicationsModule {
     fn queery(
         &self,
         input: &str,
         max_returned: u32,
     ) -> Vec<searchthing_interface::SearchItemHandle> {
-        let mut matches = vec![];
-        for (idx, wrapped) in self.entries.iter().enumerate() {
-            if let Some((s, _)) = FuzzySearch::match_idxs(&wrapped.search_text, input) {
-                matches.push((s, SearchItemHandle(idx as i32)));
+        let (category, input) = split_category(input);
+        match &category {
+            None if input.is_empty() => return self.browse_categories(),
+            Some(category) if input.is_empty() => {
+                return self.browse_category(category, max_returned)
             }
+            _ => {}
         }
-        matches.sort_by_key(|i| i.0);
-        matches
-            .into_iter()
-            .rev()
-            .take(max_returned as usize)
-            .map(|i| i.1)
-            .collect()
+
+        *self.browsed_count.lock().unwrap() = None;
+        let in_category = |idx: usize| match &category {
+            Some(category) => self.entries[idx].in_category(category),
+            None => true,
+        };
+        self.index
+            .search_filtered(input, self.match_mode, max_returned as usize, in_category)
     }
 
     fn mod_info(&self) -> searchthing_interface::SearcherInfo {
@@ -74,6 +197,13 @@ impl SearchModule for ApplicationsModule {
     }
 
     fn match_info(&self, item: SearchItemHandle) -> searchthing_interface::MatchInfo {
+        if let Some(category) = self.category(item) {
+            return searchthing_interface::MatchInfo {
+                name: category.name,
+                desc: &category.desc,
+                icon: category.icon,
+            };
+        }
         let w = self.entries.get(item.0 as usize).unwrap();
         // NOTE: handles should be valid, as they should have been obtained through the queery fn
         searchthing_interface::MatchInfo {
@@ -86,29 +216,22 @@ impl SearchModule for ApplicationsModule {
         }
     }
 
+    fn completion(&self, item: SearchItemHandle) -> Option<String> {
+        self.category(item)
+            .map(|category| format!("{CATEGORY_PREFIX}{} ", category.name.to_lowercase()))
+    }
+
     fn handle_selection(&self, selection: SearchItemHandle) {
-        let w = self.entries.get(selection.0 as usize).unwrap();
+        let Some(w) = self.entries.get(selection.0 as usize) else {
+            // a category, which is handled through completion
+            return;
+        };
         info!(
             "Selected {}, which has search text: {}",
             w.entry.name, w.search_text
         );
         if w.entry.term {
-            let term = env::var("TERMINAL").or(env::var("TERM"));
-            match term {
-                Ok(term) => {
-                    let mut cmd = Command::new(term);
-                    cmd.arg("-e").arg(w.entry.exec.trim());
-                    if let Err(why) = cmd.spawn() {
-                        error!(
-                            "Error: {why}, running desktop entry {:?} (with term, cmd: {cmd:?})",
-                            &w.entry
-                        );
-                    }
-                }
-                Err(_) => {
-                    error!("Expecting $TERMINAL or $TERM to be set for running terminal programs.")
-                }
-            }
+            run_in_terminal(&w.entry);
         } else {
             let current_dir = if w.entry.path.as_ref().map(|p| p.exists()).unwrap_or(false) {
                 w.entry.path.as_ref().unwrap()
@@ -126,4 +249,60 @@ impl SearchModule for ApplicationsModule {
             }
         }
     }
+
+    /// runs the entry in a terminal, e.g. to see the output of a graphical program
+    fn handle_secondary_selection(&self, selection: SearchItemHandle) {
+        if let Some(w) = self.entries.get(selection.0 as usize) {
+            run_in_terminal(&w.entry);
+        }
+    }
+
+    fn preview(&self, item: SearchItemHandle) -> Option<String> {
+        let w = self.entries.get(item.0 as usize)?;
+        let mut preview = format!(
+            "{}\n\nid: {}\nexec: {}",
+            w.entry.name, w.entry.id, w.entry.exec
+        );
+        if let Some(path) = &w.entry.path {
+            preview = format!("{preview}\npath: {path:?}");
+        }
+        if !w.entry.categories.is_empty() {
+            preview = format!("{preview}\ncategories: {}", w.entry.categories.join(", "));
+        }
+        if !w.entry.keywords.is_empty() {
+            preview = format!("{preview}\nkeywords: {}", w.entry.keywords.join(", "));
+        }
+        Some(preview)
+    }
+
+    /// the entries and the categories browsed through
+    fn item_count(&self) -> Option<usize> {
+        Some(self.entries.len() + self.categories.len())
+    }
+
+    fn match_count(&self) -> Option<usize> {
+        let browsed = *self.browsed_count.lock().unwrap();
+        Some(browsed.unwrap_or(self.index.match_count()))
+    }
+
+    fn set_match_mode(&mut self, mode: MatchMode) -> bool {
+        self.match_mode = mode;
+        true
+    }
+}
+
+fn run_in_terminal(entry: &DesktopEntry) {
+    let term = env::var("TERMINAL").or(env::var("TERM"));
+    match term {
+        Ok(term) => {
+            let mut cmd = Command::new(term);
+            cmd.arg("-e").arg(entry.exec.trim());
+            if let Err(why) = cmd.spawn() {
+                error!("Error: {why}, running desktop entry {entry:?} (with term, cmd: {cmd:?})");
+            }
+        }
+        Err(_) => {
+            error!("Expecting $TERMINAL or $TERM to be set for running terminal programs.")
+        }
+    }
 }
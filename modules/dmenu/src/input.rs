@@ -0,0 +1,156 @@
+//! Parsing of the option lines read from stdin, either plain text (optionally with rofi's row
+//! options), delimiter separated columns or JSON objects.
+
+use serde::Deserialize;
+
+/// A column of delimiter separated input
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Column {
+    Name,
+    Desc,
+    Icon,
+    Output,
+}
+
+impl Column {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "name" => Some(Self::Name),
+            "desc" => Some(Self::Desc),
+            "icon" => Some(Self::Icon),
+            "output" => Some(Self::Output),
+            _ => None,
+        }
+    }
+}
+
+/// How each line of input describes an option
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum InputFormat {
+    /// the option as is, or with rofi's row options after a null byte, e.g.
+    /// `firefox\0icon\x1ffirefox\x1fdisplay\x1fFirefox`
+    #[default]
+    Lines,
+    /// fields in the given order, e.g. with a tab delimiter `id1\tFirefox\tweb browser`
+    Delimited {
+        delimiter: String,
+        columns: Vec<Column>,
+    },
+    /// an object per line, e.g. `{"name": "Firefox", "icon": "firefox", "output": "id1"}`
+    Json,
+}
+
+/// An option as shown, searched and printed
+#[derive(Debug, Default, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct DmenuOption {
+    pub name: String,
+    pub desc: String,
+    pub icon: String,
+    /// printed when selected instead of the name, e.g. an id
+    pub output: Option<String>,
+    /// searched, but not shown
+    pub keywords: String,
+}
+
+impl DmenuOption {
+    fn plain(line: &str) -> Self {
+        Self {
+            name: line.into(),
+            ..Default::default()
+        }
+    }
+
+    pub fn parse(line: &str, format: &InputFormat) -> Self {
+        match format {
+            InputFormat::Lines => Self::parse_rofi(line),
+            InputFormat::Delimited { delimiter, columns } => {
+                let mut option = Self::default();
+                for (column, field) in columns.iter().zip(line.split(delimiter.as_str())) {
+                    let field = field.to_string();
+                    match column {
+                        Column::Name => option.name = field,
+                        Column::Desc => option.desc = field,
+                        Column::Icon => option.icon = field,
+                        Column::Output => option.output = Some(field),
+                    }
+                }
+                option
+            }
+            // NOTE: not skipped when invalid, so that line indices stay those of the input
+            InputFormat::Json => serde_json::from_str(line).unwrap_or_else(|_| Self::plain(line)),
+        }
+    }
+
+    /// rofi's `text\0key\x1fvalue\x1f...` row options, of which icon, display (shown instead of
+    /// the text) and meta (searched, but not shown) are used
+    fn parse_rofi(line: &str) -> Self {
+        let Some((text, row_options)) = line.split_once('\0') else {
+            return Self::plain(line);
+        };
+        let mut option = Self::plain(text);
+        let mut fields = row_options.split('\x1f');
+        while let (Some(key), Some(value)) = (fields.next(), fields.next()) {
+            match key {
+                "icon" => option.icon = value.into(),
+                "display" => {
+                    option.output = Some(text.into());
+                    option.name = value.into();
+                }
+                "meta" => option.keywords = value.into(),
+                _ => {}
+            }
+        }
+        option
+    }
+
+    /// the text printed when selected
+    pub fn output(&self) -> &str {
+        self.output.as_deref().unwrap_or(&self.name)
+    }
+}
+
+#[test]
+fn parse_option_test() {
+    let option = DmenuOption::parse(
+        "ff\0icon\x1ffirefox\x1fdisplay\x1fFirefox",
+        &InputFormat::Lines,
+    );
+    assert_eq!(
+        (option.name.as_str(), option.icon.as_str()),
+        ("Firefox", "firefox")
+    );
+    assert_eq!(option.output(), "ff");
+    assert_eq!(DmenuOption::parse("a\tb", &InputFormat::Lines).name, "a\tb");
+
+    let delimited = InputFormat::Delimited {
+        delimiter: "\t".into(),
+        columns: vec![Column::Output, Column::Name, Column::Desc],
+    };
+    let option = DmenuOption::parse("id1\tFirefox", &delimited);
+    assert_eq!(
+        (option.name.as_str(), option.desc.as_str()),
+        ("Firefox", "")
+    );
+    assert_eq!(option.output(), "id1");
+    // without an output column, the name is printed, as with JSON lines without an output
+    let no_output = InputFormat::Delimited {
+        delimiter: ":".into(),
+        columns: vec![Column::Name, Column::Desc],
+    };
+    assert_eq!(
+        DmenuOption::parse("Firefox:web:extra", &no_output).output(),
+        "Firefox"
+    );
+
+    let option = DmenuOption::parse(r#"{"name": "Firefox", "desc": "web"}"#, &InputFormat::Json);
+    assert_eq!(
+        (option.name.as_str(), option.desc.as_str()),
+        ("Firefox", "web")
+    );
+    assert_eq!(option.output(), "Firefox");
+    assert_eq!(
+        DmenuOption::parse("{oops", &InputFormat::Json).name,
+        "{oops"
+    );
+}
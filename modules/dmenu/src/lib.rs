@@ -1,30 +1,139 @@
-use std::io::stdin;
+use std::{
+    fmt::Display,
+    io::{stdin, stdout, BufRead, Write},
+    sync::mpsc::{channel, Receiver, TryRecvError},
+    thread,
+    time::{Duration, Instant},
+};
 
-use searchthing_interface::{FuzzySearch, SearchItemHandle, SearchMethod, SearchModule};
+use searchthing_interface::{MatchMode, SearchIndex, SearchItemHandle, SearchModule};
 
-static EMPTY: &str = "";
+mod input;
+
+/// limits on how many options a single poll takes in
+const POLL_MAX_OPTIONS: usize = 10_000;
+const POLL_BUDGET: Duration = Duration::from_millis(8);
+
+pub use input::{Column, DmenuOption, InputFormat};
+
+/// What is printed for a selected option
+#[derive(Clone, Copy, Default, Debug, PartialEq)]
+pub enum OutputFormat {
+    /// the option's text (or its output field, with structured input)
+    #[default]
+    Text,
+    /// its line number, counting from 0
+    Index,
+    /// its line number, counting from 1
+    Number,
+}
+
+impl OutputFormat {
+    /// from rofi's -format letters, "s", "i" or "d"
+    pub fn from_rofi(format: &str) -> Option<Self> {
+        match format {
+            "s" => Some(Self::Text),
+            "i" => Some(Self::Index),
+            "d" => Some(Self::Number),
+            _ => None,
+        }
+    }
+}
+
+pub struct DmenuOptions {
+    pub prompt: Option<String>,
+    pub format: OutputFormat,
+    pub input_format: InputFormat,
+    /// print the search text when selected as is (e.g. when it matches nothing)
+    pub allow_custom: bool,
+    /// end printed options with a null byte rather than a newline
+    pub null_separated: bool,
+    /// what separates options in the input, a newline by default
+    pub separator: u8,
+}
+
+impl Default for DmenuOptions {
+    fn default() -> Self {
+        Self {
+            prompt: None,
+            format: Default::default(),
+            input_format: Default::default(),
+            allow_custom: true,
+            null_separated: false,
+            separator: b'\n',
+        }
+    }
+}
 
 pub struct DmenuModule {
     name: String,
     icon: String,
-    options: Vec<String>,
+    options: Vec<DmenuOption>,
+    /// the name, description and keywords of each option, as matched
+    index: SearchIndex,
+    /// options still being read from stdin, until it is closed
+    incoming: Option<Receiver<DmenuOption>>,
+    format: OutputFormat,
+    allow_custom: bool,
+    separator: char,
+    match_mode: MatchMode,
 }
 
 impl DmenuModule {
-    pub fn new(prompt: Option<String>) -> Self {
-        let options = stdin()
-            .lines()
-            .filter_map(|lr| match lr {
-                Ok(l) => Some(l),
-                Err(_) => None,
-            })
-            .collect();
+    pub fn new(
+        DmenuOptions {
+            prompt,
+            format,
+            input_format,
+            allow_custom,
+            null_separated,
+            separator: input_separator,
+        }: DmenuOptions,
+    ) -> Self {
+        // NOTE: read in the background, so that options from a slow command show up as they come
+        let (sender, incoming) = channel();
+        thread::spawn(move || {
+            let options = stdin()
+                .lock()
+                .split(input_separator)
+                .map_while(Result::ok)
+                .map(|bytes| {
+                    let text = String::from_utf8_lossy(&bytes);
+                    let trimmed = match input_separator {
+                        // NOTE: as lines would, for windows line endings
+                        b'\n' => text.strip_suffix('\r'),
+                        // e.g. the newline that echo ends the last option with
+                        _ => text.strip_suffix('\n'),
+                    };
+                    DmenuOption::parse(trimmed.unwrap_or(&text), &input_format)
+                });
+            for option in options {
+                if sender.send(option).is_err() {
+                    break;
+                }
+            }
+        });
         Self {
             name: prompt.unwrap_or("Dmenu".into()),
             icon: "system-search".into(),
-            options,
+            options: vec![],
+            index: Default::default(),
+            incoming: Some(incoming),
+            format,
+            allow_custom,
+            separator: match null_separated {
+                true => '\0',
+                false => '\n',
+            },
+            match_mode: Default::default(),
         }
     }
+
+    fn print(&self, output: impl Display) {
+        let mut stdout = stdout().lock();
+        // NOTE: flushed, as null separated output is otherwise held back until exit
+        let _ = write!(stdout, "{output}{}", self.separator).and_then(|_| stdout.flush());
+    }
 }
 
 impl SearchModule for DmenuModule {
@@ -33,19 +142,8 @@ impl SearchModule for DmenuModule {
         input: &str,
         max_returned: u32,
     ) -> Vec<searchthing_interface::SearchItemHandle> {
-        let mut matches = vec![];
-        for (idx, opt) in self.options.iter().enumerate() {
-            if let Some((s, _)) = FuzzySearch::match_idxs(&opt.to_lowercase(), input) {
-                matches.push((s, SearchItemHandle(idx as i32)));
-            }
-        }
-        matches.sort_by_key(|i| i.0);
-        matches
-            .into_iter()
-            .rev()
-            .take(max_returned as usize)
-            .map(|i| i.1)
-            .collect()
+        self.index
+            .search(input, self.match_mode, max_returned as usize)
     }
 
     fn mod_info(&self) -> searchthing_interface::SearcherInfo {
@@ -59,14 +157,82 @@ impl SearchModule for DmenuModule {
         let opt = self.options.get(item.0 as usize).unwrap();
         // NOTE: handles should be valid, as they should have been obtained through the queery fn
         searchthing_interface::MatchInfo {
-            name: opt,
-            desc: EMPTY,
-            icon: EMPTY,
+            name: &opt.name,
+            desc: &opt.desc,
+            icon: &opt.icon,
         }
     }
 
     fn handle_selection(&self, selection: SearchItemHandle) {
         let opt = self.options.get(selection.0 as usize).unwrap();
-        println!("{}", opt);
+        match self.format {
+            OutputFormat::Text => self.print(opt.output()),
+            OutputFormat::Index => self.print(selection.0),
+            OutputFormat::Number => self.print(selection.0 + 1),
+        }
+    }
+
+    /// prints the input as is, or an index of -1 (0 counting from 1) as rofi does
+    fn handle_custom_input(&self, input: &str) -> bool {
+        if !self.allow_custom {
+            return false;
+        }
+        match self.format {
+            OutputFormat::Text => self.print(input),
+            OutputFormat::Index => self.print(-1),
+            OutputFormat::Number => self.print(0),
+        }
+        true
+    }
+
+    fn poll_updates(&mut self) -> bool {
+        let Some(incoming) = &self.incoming else {
+            return false;
+        };
+        let count = self.options.len();
+        // NOTE: bounded so that a fast producer cannot hold up the frame until EOF, the rest is
+        // taken in on the next poll
+        let start = Instant::now();
+        while self.options.len() - count < POLL_MAX_OPTIONS && start.elapsed() < POLL_BUDGET {
+            match incoming.try_recv() {
+                Ok(option) => {
+                    self.index
+                        .push([&option.name, &option.desc, &option.keywords]);
+                    self.options.push(option);
+                }
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    self.incoming = None;
+                    break;
+                }
+            }
+        }
+        self.options.len() > count
+    }
+
+    fn loading(&self) -> bool {
+        self.incoming.is_some()
+    }
+
+    fn item_count(&self) -> Option<usize> {
+        Some(self.options.len())
+    }
+
+    fn match_count(&self) -> Option<usize> {
+        Some(self.index.match_count())
+    }
+
+    fn set_match_mode(&mut self, mode: MatchMode) -> bool {
+        self.match_mode = mode;
+        true
+    }
+
+    /// the whole name and description, in case they are cut off in the list
+    fn preview(&self, item: SearchItemHandle) -> Option<String> {
+        let opt = self.options.get(item.0 as usize)?;
+        match opt.desc.is_empty() {
+            true => Some(opt.name.clone()),
+            false => Some(format!("{}\n\n{}", opt.name, opt.desc)),
+        }
     }
 }
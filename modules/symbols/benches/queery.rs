@@ -0,0 +1,32 @@
+//! Latency of queeries over all the symbols, made on every keystroke as they are typed out, with
+//! `cargo bench -p symbols --bench queery`
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use searchthing_interface::{MatchMode, SearchModule};
+use std::hint::black_box;
+use symbols::SymbolsModule;
+
+static QUEERIES: &[&str] = &["arrow", "right arrow", "greek small", "zzzq"];
+
+fn queery(c: &mut Criterion) {
+    let mut module = SymbolsModule::default();
+    for mode in [MatchMode::Fuzzy, MatchMode::Substring, MatchMode::Extended] {
+        module.set_match_mode(mode);
+        let mut group = c.benchmark_group(format!("symbols/{}", mode.name()));
+        for queery in QUEERIES {
+            group.bench_with_input(BenchmarkId::from_parameter(queery), queery, |b, queery| {
+                // NOTE: starting over each time, as the first char does not narrow down the last
+                b.iter(|| {
+                    for (end, _) in queery.char_indices().skip(1) {
+                        module.queery(black_box(&queery[..end]), 50);
+                    }
+                    module.queery(black_box(queery), 50)
+                })
+            });
+        }
+        group.finish();
+    }
+}
+
+criterion_group!(benches, queery);
+criterion_main!(benches);
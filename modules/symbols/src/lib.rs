@@ -1,7 +1,8 @@
 use std::process::Command;
 
+use log::error;
 use searchthing_interface::{
-    char_from_codepoint, FuzzySearch, SearchItemHandle, SearchMethod, SearchModule,
+    char_from_codepoint, MatchMode, SearchIndex, SearchItemHandle, SearchModule,
 };
 
 static CODEPOINTS: &str = include_str!("../codepoints");
@@ -16,12 +17,14 @@ pub struct SymbolsModule {
     name: String,
     icon: String,
     options: Vec<LabeledCodepoints>,
+    index: SearchIndex,
+    match_mode: MatchMode,
 }
 
 impl Default for SymbolsModule {
     fn default() -> Self {
         // TODO: make this a compile time calculated ArrayVec?
-        let options = CODEPOINTS
+        let options: Vec<_> = CODEPOINTS
             .lines()
             .map(|line| {
                 let sp = line.split(';').collect::<Vec<_>>();
@@ -32,10 +35,16 @@ impl Default for SymbolsModule {
                 }
             })
             .collect();
+        let mut index = SearchIndex::default();
+        for opt in &options {
+            index.push([opt.primary_label, opt.secondary_label]);
+        }
         Self {
             name: "Symbols".into(),
             icon: "emoji-symbols-symbolic".into(),
             options,
+            index,
+            match_mode: Default::default(),
         }
     }
 }
@@ -46,24 +55,8 @@ impl SearchModule for SymbolsModule {
         input: &str,
         max_returned: u32,
     ) -> Vec<searchthing_interface::SearchItemHandle> {
-        let mut matches = vec![];
-        for (idx, opt) in self.options.iter().enumerate() {
-            if let Some((s, _)) = FuzzySearch::match_idxs(&opt.primary_label.to_lowercase(), input)
-            {
-                matches.push((s, SearchItemHandle(idx as i32)));
-            } else if let Some((s, _)) =
-                FuzzySearch::match_idxs(&opt.secondary_label.to_lowercase(), input)
-            {
-                matches.push((s, SearchItemHandle(idx as i32)));
-            }
-        }
-        matches.sort_by_key(|i| i.0);
-        matches
-            .into_iter()
-            .rev()
-            .take(max_returned as usize)
-            .map(|i| i.1)
-            .collect()
+        self.index
+            .search(input, self.match_mode, max_returned as usize)
     }
 
     fn mod_info(&self) -> searchthing_interface::SearcherInfo {
@@ -86,7 +79,44 @@ impl SearchModule for SymbolsModule {
     fn handle_selection(&self, selection: SearchItemHandle) {
         let opt = self.options.get(selection.0 as usize).unwrap();
         if let Some(c) = char_from_codepoint(opt.codepoint) {
-            Command::new("wl-copy").arg(c.to_string()).spawn().unwrap();
+            copy(&c.to_string());
         }
     }
+
+    /// copies the codepoint rather than the symbol
+    fn handle_secondary_selection(&self, selection: SearchItemHandle) {
+        let opt = self.options.get(selection.0 as usize).unwrap();
+        copy(opt.codepoint);
+    }
+
+    fn preview(&self, item: SearchItemHandle) -> Option<String> {
+        let opt = self.options.get(item.0 as usize)?;
+        let symbol = char_from_codepoint(opt.codepoint)?;
+        Some(format!(
+            "{symbol}\n\n{}\n{}\n{}",
+            opt.primary_label, opt.secondary_label, opt.codepoint
+        ))
+    }
+
+    fn item_count(&self) -> Option<usize> {
+        Some(self.options.len())
+    }
+
+    fn match_count(&self) -> Option<usize> {
+        Some(self.index.match_count())
+    }
+
+    fn set_match_mode(&mut self, mode: MatchMode) -> bool {
+        self.match_mode = mode;
+        true
+    }
+}
+
+/// copies text to the clipboard, waiting on wl-copy, which forks to keep serving it
+fn copy(text: &str) {
+    match Command::new("wl-copy").arg(text).status() {
+        Ok(status) if !status.success() => error!("wl-copy failed with {status}"),
+        Err(why) => error!("Error: {why}, running wl-copy (is it installed?)"),
+        Ok(_) => {}
+    }
 }
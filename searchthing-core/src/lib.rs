@@ -0,0 +1,11 @@
+//! The frontend agnostic parts of SearchThing: the active modules and their matches, keyboard
+//! navigation through them and acting on selections, driven through a [Session] by the egui
+//! dialogue, the terminal frontend or the headless commands.
+
+mod navigation;
+mod searcher;
+mod session;
+
+pub use navigation::{edit_line, navigate, KeyCommand};
+pub use searcher::{best_match, Selection, WrappedSearcher};
+pub use session::{Outcome, Session};
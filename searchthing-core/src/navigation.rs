@@ -0,0 +1,199 @@
+//! Keyboard commands, and navigation through the matches of all modules as a flat list.
+
+/// Rows moved by PageUp/PageDown.
+const PAGE_SIZE: usize = 5;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum KeyCommand {
+    Prev,
+    Next,
+    PageUp,
+    PageDown,
+    First,
+    Last,
+    /// first match of the next module with any matches
+    NextModule,
+    PrevModule,
+    /// select the nth match (0 indexed)
+    Pick(usize),
+    Select,
+    SelectStayOpen,
+    SecondaryAction,
+    /// select the search text as is, rather than the highlighted match
+    AcceptCustom,
+    /// select, then exit with status 9 + n, like rofi's custom keybindings (1 to 19)
+    Custom(u8),
+    /// switch modules to the next way of matching, e.g. from fuzzy to prefix
+    CycleMatchMode,
+    /// mark or unmark the highlighted match to be selected together with others, then move on
+    ToggleMark,
+    ClearInput,
+    Exit,
+    TogglePreview,
+    /// Emacs style line editing of the search input
+    LineStart,
+    LineEnd,
+    DeleteToStart,
+    DeleteWord,
+}
+
+impl KeyCommand {
+    /// the exit status after a selection with this command
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Self::Custom(n) => 9 + *n as i32,
+            _ => 0,
+        }
+    }
+    /// commands acting on the search input, rather than the matches
+    pub fn is_line_edit(&self) -> bool {
+        matches!(
+            self,
+            Self::LineStart
+                | Self::LineEnd
+                | Self::DeleteToStart
+                | Self::DeleteWord
+                | Self::ClearInput
+        )
+    }
+    /// the name used in the config file, e.g. "select-stay-open", "pick-3" or "custom-1"
+    pub fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "prev" => Self::Prev,
+            "next" => Self::Next,
+            "page-up" => Self::PageUp,
+            "page-down" => Self::PageDown,
+            "first" => Self::First,
+            "last" => Self::Last,
+            "next-module" => Self::NextModule,
+            "prev-module" => Self::PrevModule,
+            "select" => Self::Select,
+            "select-stay-open" => Self::SelectStayOpen,
+            "secondary-action" => Self::SecondaryAction,
+            "accept-custom" => Self::AcceptCustom,
+            "toggle-mark" => Self::ToggleMark,
+            "cycle-match-mode" => Self::CycleMatchMode,
+            "clear-input" => Self::ClearInput,
+            "exit" => Self::Exit,
+            "toggle-preview" => Self::TogglePreview,
+            "line-start" => Self::LineStart,
+            "line-end" => Self::LineEnd,
+            "delete-to-start" => Self::DeleteToStart,
+            "delete-word" => Self::DeleteWord,
+            _ => match name.split_once('-')? {
+                ("pick", n) => Self::Pick(n.parse::<usize>().ok()?.checked_sub(1)?),
+                ("custom", n) => match n.parse().ok()? {
+                    n @ 1..=19 => Self::Custom(n),
+                    _ => return None,
+                },
+                _ => return None,
+            },
+        })
+    }
+}
+
+/// Applies a navigation command to the highlighted index, returns the new index and whether it
+/// should be selected. Moving up or down wraps around, paging stops at either end. Other commands
+/// leave the index as is.
+pub fn navigate(command: KeyCommand, idx: usize, match_counts: &[usize]) -> (usize, bool) {
+    let total: usize = match_counts.iter().sum();
+    if total == 0 {
+        return (0, false);
+    }
+    let idx = idx.min(total - 1);
+    // index of the first match of each module with matches
+    let module_starts: Vec<usize> = match_counts
+        .iter()
+        .scan(0, |start, count| {
+            let s = *start;
+            *start += count;
+            Some((s, *count))
+        })
+        .filter(|(_, count)| *count > 0)
+        .map(|(s, _)| s)
+        .collect();
+    let current_module = module_starts.iter().rposition(|s| *s <= idx).unwrap_or(0);
+    match command {
+        KeyCommand::Prev => ((idx + total - 1) % total, false),
+        KeyCommand::Next => ((idx + 1) % total, false),
+        KeyCommand::PageUp => (idx.saturating_sub(PAGE_SIZE), false),
+        KeyCommand::PageDown => ((idx + PAGE_SIZE).min(total - 1), false),
+        KeyCommand::First => (0, false),
+        KeyCommand::Last => (total - 1, false),
+        KeyCommand::NextModule => (
+            module_starts[(current_module + 1) % module_starts.len()],
+            false,
+        ),
+        KeyCommand::PrevModule => {
+            let n = module_starts.len();
+            (module_starts[(current_module + n - 1) % n], false)
+        }
+        KeyCommand::Pick(n) if n < total => (n, true),
+        _ => (idx, false),
+    }
+}
+
+#[test]
+fn navigate_test() {
+    let counts = [3, 0, 2];
+    assert_eq!(navigate(KeyCommand::Next, 0, &[0, 0]), (0, false));
+    assert_eq!(navigate(KeyCommand::Pick(0), 0, &[]), (0, false));
+    assert_eq!(navigate(KeyCommand::Prev, 0, &counts), (4, false));
+    assert_eq!(navigate(KeyCommand::Next, 4, &counts), (0, false));
+    assert_eq!(navigate(KeyCommand::PageDown, 1, &counts), (4, false));
+    assert_eq!(navigate(KeyCommand::NextModule, 1, &counts), (3, false));
+    assert_eq!(navigate(KeyCommand::NextModule, 4, &counts), (0, false));
+    assert_eq!(navigate(KeyCommand::PrevModule, 0, &counts), (3, false));
+    assert_eq!(navigate(KeyCommand::Pick(2), 0, &counts), (2, true));
+    assert_eq!(navigate(KeyCommand::Pick(7), 1, &counts), (1, false));
+    // index left over from a longer list of matches
+    assert_eq!(navigate(KeyCommand::Prev, 9, &counts), (3, false));
+}
+
+/// Applies an Emacs style editing command to the search input, given the cursor position (in
+/// chars), returns the new cursor position.
+pub fn edit_line(command: KeyCommand, text: &mut String, cursor: usize) -> usize {
+    let byte_idx = |c: usize| text.char_indices().nth(c).map_or(text.len(), |(i, _)| i);
+    let cursor_byte = byte_idx(cursor);
+    match command {
+        KeyCommand::LineStart => 0,
+        KeyCommand::LineEnd => text.chars().count(),
+        KeyCommand::ClearInput => {
+            text.clear();
+            0
+        }
+        KeyCommand::DeleteToStart => {
+            text.replace_range(..cursor_byte, "");
+            0
+        }
+        KeyCommand::DeleteWord => {
+            let before = &text[..cursor_byte];
+            let start = before
+                .trim_end()
+                .char_indices()
+                .rev()
+                .find(|(_, c)| c.is_whitespace())
+                .map_or(0, |(i, c)| i + c.len_utf8());
+            let removed = text[start..cursor_byte].chars().count();
+            text.replace_range(start..cursor_byte, "");
+            cursor - removed
+        }
+        _ => cursor,
+    }
+}
+
+#[test]
+fn edit_line_test() {
+    let mut text = String::from("cat:dev  code");
+    assert_eq!(edit_line(KeyCommand::DeleteWord, &mut text, 13), 9);
+    assert_eq!(text, "cat:dev  ");
+    assert_eq!(edit_line(KeyCommand::DeleteWord, &mut text, 9), 0);
+    assert_eq!(text, "");
+    let mut text = String::from("föö bar");
+    assert_eq!(edit_line(KeyCommand::DeleteToStart, &mut text, 4), 0);
+    assert_eq!(text, "bar");
+    // multi-byte whitespace
+    let mut text = String::from("foo\u{a0}bar");
+    assert_eq!(edit_line(KeyCommand::DeleteWord, &mut text, 7), 4);
+    assert_eq!(text, "foo\u{a0}");
+}
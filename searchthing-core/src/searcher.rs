@@ -0,0 +1,149 @@
+use searchthing_interface::{
+    FoldedText, MatchInfo, MatchMode, SearchItemHandle, SearchModule, SearcherInfo,
+};
+
+/// How a match was selected
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Selection {
+    Primary,
+    /// primary, but without closing afterwards
+    StayOpen,
+    Secondary,
+}
+
+/// Whether the name or description of a match is the better match for the queery, with its score
+/// and matched char indices (of the name or description as given, e.g. with diacritics).
+pub fn best_match(
+    match_name: &str,
+    desc: &str,
+    queery: &str,
+    mode: MatchMode,
+) -> Option<(bool, i64, Vec<usize>)> {
+    let queery = mode.fold_queery(queery);
+    let name_mtch = mode.match_folded(&FoldedText::new(match_name), &queery);
+    let desc_mtch = mode.match_folded(&FoldedText::new(desc), &queery);
+    match (name_mtch, desc_mtch) {
+        (Some((ns, nidxs)), Some((ds, didxs))) => match ns > ds {
+            true => Some((true, ns, nidxs)),
+            false => Some((false, ds, didxs)),
+        },
+        (Some((s, idxs)), None) => Some((true, s, idxs)),
+        (None, Some((s, idxs))) => Some((false, s, idxs)),
+        (None, None) => None,
+    }
+}
+
+/// A module, with the matches of the last queery
+pub struct WrappedSearcher {
+    searcher: Box<dyn SearchModule>,
+    name: String,
+    /// identifies the module in match ids
+    key: String,
+    icon: String,
+    cached_matches: Vec<SearchItemHandle>,
+    max_shown: u32,
+    /// none if the module only matches fuzzily
+    match_mode: Option<MatchMode>,
+}
+
+impl WrappedSearcher {
+    pub fn new(mut searcher: impl SearchModule + 'static, max_shown: u32) -> Self {
+        let match_mode = searcher
+            .set_match_mode(MatchMode::default())
+            .then_some(MatchMode::default());
+        let SearcherInfo { name, icon } = searcher.mod_info();
+        Self {
+            match_mode,
+            key: name.to_lowercase().replace(char::is_whitespace, "-"),
+            name: name.into(),
+            icon: icon.into(),
+            searcher: Box::new(searcher),
+            cached_matches: Default::default(),
+            max_shown,
+        }
+    }
+    pub fn queery(&mut self, input: &str) {
+        self.cached_matches = self.searcher.queery(input, self.max_shown);
+    }
+    /// takes in any new items of the module, rerunning the queery if there were some
+    pub fn poll_updates(&mut self, input: &str) -> bool {
+        let updated = self.searcher.poll_updates();
+        if updated {
+            self.queery(input);
+        }
+        updated
+    }
+    /// the mode the module matches with, if it offers a choice
+    pub fn match_mode(&self) -> Option<MatchMode> {
+        self.match_mode
+    }
+    /// takes effect on the next queery, if the module offers a choice
+    pub fn set_match_mode(&mut self, mode: MatchMode) {
+        if self.searcher.set_match_mode(mode) {
+            self.match_mode = Some(mode);
+        }
+    }
+    pub fn cached_matches(&self) -> &Vec<SearchItemHandle> {
+        &self.cached_matches
+    }
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+    pub fn icon(&self) -> &str {
+        &self.icon
+    }
+    /// the module name as used in match ids, e.g. "applications"
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+    /// overrides the key derived from the module name (e.g. when it is a user given prompt)
+    pub fn with_key(mut self, key: &str) -> Self {
+        self.key = key.into();
+        self
+    }
+    pub fn module(&self) -> &dyn SearchModule {
+        self.searcher.as_ref()
+    }
+    /// NOTE: changes made through this are not reflected in the cached matches until the next
+    /// queery
+    pub fn module_mut(&mut self) -> &mut dyn SearchModule {
+        self.searcher.as_mut()
+    }
+    pub fn match_info(&self, i: usize) -> Option<MatchInfo> {
+        Some(self.searcher.match_info(*self.cached_matches.get(i)?))
+    }
+    /// Acts on the ith cached match, returns its completion instead if it provides one.
+    pub fn select(&self, i: usize, selection: Selection) -> Option<String> {
+        let handle = *self.cached_matches.get(i)?;
+        if selection == Selection::Secondary {
+            self.searcher.handle_secondary_selection(handle);
+            return None;
+        }
+        let completion = self.searcher.completion(handle);
+        if completion.is_none() {
+            self.searcher.handle_selection(handle);
+        }
+        completion
+    }
+    /// the match mode unless fuzzy, and the number of matches out of the module's items if it
+    /// counts them, e.g. "regex 10/340 loading…"
+    pub fn status(&self) -> Option<String> {
+        let mut status = vec![];
+        if let Some(mode) = self.match_mode.filter(|m| *m != MatchMode::Fuzzy) {
+            status.push(mode.name().to_string());
+        }
+        if let Some(count) = self.searcher.item_count() {
+            let matched = self.searcher.match_count();
+            let matched = matched.unwrap_or(self.cached_matches.len());
+            status.push(format!("{matched}/{count}"));
+        }
+        if self.searcher.loading() {
+            status.push("loading…".into());
+        }
+        (!status.is_empty()).then(|| status.join(" "))
+    }
+    /// the preview of the ith cached match
+    pub fn preview(&self, i: usize) -> Option<String> {
+        self.searcher.preview(*self.cached_matches.get(i)?)
+    }
+}
@@ -0,0 +1,290 @@
+use crate::{
+    navigation::{navigate, KeyCommand},
+    searcher::{best_match, Selection, WrappedSearcher},
+};
+use searchthing_interface::SearchItemHandle;
+
+/// What a frontend should do after a selection
+#[derive(Debug, PartialEq)]
+pub enum Outcome {
+    /// there was no match to act on
+    Nothing,
+    Close,
+    StayOpen,
+    /// the queery was replaced by the completion of the selected match (e.g. to drill down into a
+    /// category), to be shown in the search input
+    Completed(String),
+}
+
+/// A search over a set of modules: the queery, the matches of each module and the highlighted
+/// match. Matches are addressed by searcher and match index, and listed as rows, either grouped by
+/// module or merged into one list by score.
+pub struct Session {
+    searchers: Vec<WrappedSearcher>,
+    queery: String,
+    /// searcher and match index of each row, in the order shown
+    rows: Vec<(usize, usize)>,
+    /// the highlighted row
+    cursor: usize,
+    /// a row to highlight once enough matches have loaded
+    pending_cursor: Option<usize>,
+    stay_open: bool,
+    merged: bool,
+    multi_select: bool,
+    /// searcher index and handle of the marked matches, which stay marked across queeries
+    marks: Vec<(usize, SearchItemHandle)>,
+}
+
+impl Session {
+    /// queries the modules with the initial text straight away, as they may offer something to
+    /// browse with an empty queery
+    pub fn new(searchers: Vec<WrappedSearcher>, queery: &str, stay_open: bool) -> Self {
+        let mut session = Self {
+            searchers,
+            queery: queery.into(),
+            rows: vec![],
+            cursor: 0,
+            pending_cursor: None,
+            stay_open,
+            merged: false,
+            multi_select: false,
+            marks: vec![],
+        };
+        session.requery();
+        session
+    }
+    /// list the matches of all modules together, ordered by score
+    pub fn merged(mut self, merged: bool) -> Self {
+        self.merged = merged;
+        self.update_rows();
+        self
+    }
+    /// allow marking matches with [KeyCommand::ToggleMark], to then select them all at once
+    pub fn multi_select(mut self, multi_select: bool) -> Self {
+        self.multi_select = multi_select;
+        self
+    }
+
+    pub fn searchers(&self) -> &[WrappedSearcher] {
+        &self.searchers
+    }
+    pub fn queery(&self) -> &str {
+        &self.queery
+    }
+    /// requeries the modules if the text changed
+    pub fn set_queery(&mut self, text: &str) {
+        if text != self.queery {
+            self.queery = text.into();
+            self.requery();
+        }
+    }
+    /// Takes in items that arrived since (e.g. dmenu options read from a pipe), returns whether
+    /// there were any. To be called regularly while [Session::loading].
+    pub fn poll_updates(&mut self) -> bool {
+        let mut updated = false;
+        for searcher in &mut self.searchers {
+            updated |= searcher.poll_updates(&self.queery);
+        }
+        if updated {
+            self.update_rows();
+        }
+        updated
+    }
+    /// whether any module is still loading its items
+    pub fn loading(&self) -> bool {
+        self.searchers.iter().any(|s| s.module().loading())
+    }
+    fn requery(&mut self) {
+        for searcher in &mut self.searchers {
+            searcher.queery(&self.queery);
+        }
+        self.update_rows();
+    }
+    fn update_rows(&mut self) {
+        self.rows = self
+            .searchers
+            .iter()
+            .enumerate()
+            .flat_map(|(j, s)| (0..s.cached_matches().len()).map(move |i| (j, i)))
+            .collect();
+        if self.merged {
+            // NOTE: stable, so that each module's own ranking breaks ties
+            let searchers = &self.searchers;
+            let queery = &self.queery;
+            self.rows.sort_by_cached_key(|(j, i)| {
+                let searcher = &searchers[*j];
+                let mode = searcher.match_mode().unwrap_or_default();
+                let score = searcher
+                    .match_info(*i)
+                    .and_then(|m| best_match(m.name, m.desc, queery, mode).map(|(_, s, _)| s));
+                std::cmp::Reverse(score)
+            });
+        }
+        if let Some(row) = self.pending_cursor.filter(|row| *row < self.rows.len()) {
+            self.cursor = row;
+            self.pending_cursor = None;
+        }
+        // the match list may have shrunk
+        self.cursor = self.cursor.min(self.rows.len().saturating_sub(1));
+    }
+
+    /// searcher and match index of each row, in the order shown
+    pub fn rows(&self) -> &[(usize, usize)] {
+        &self.rows
+    }
+    pub fn is_merged(&self) -> bool {
+        self.merged
+    }
+    /// the row of a match, if it is listed
+    pub fn row_of(&self, j: usize, i: usize) -> Option<usize> {
+        self.rows.iter().position(|r| *r == (j, i))
+    }
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+    /// highlights a row, once it has loaded if the matches are still loading
+    pub fn set_cursor(&mut self, row: usize) {
+        self.pending_cursor = (row >= self.rows.len() && self.loading()).then_some(row);
+        self.cursor = row.min(self.rows.len().saturating_sub(1));
+    }
+    /// searcher and match index of the highlighted match
+    pub fn highlighted(&self) -> Option<(usize, usize)> {
+        self.rows.get(self.cursor).copied()
+    }
+    pub fn is_highlighted(&self, j: usize, i: usize) -> bool {
+        self.highlighted() == Some((j, i))
+    }
+    pub fn is_marked(&self, j: usize, i: usize) -> bool {
+        let handle = self
+            .searchers
+            .get(j)
+            .and_then(|s| s.cached_matches().get(i));
+        handle.is_some_and(|h| self.marks.contains(&(j, *h)))
+    }
+    /// the number of marked matches, including those not matching the current queery
+    pub fn mark_count(&self) -> usize {
+        self.marks.len()
+    }
+    /// switches the modules offering a choice of match modes to the mode after that of the first
+    /// of them
+    fn cycle_match_mode(&mut self) {
+        let Some(mode) = self.searchers.iter().find_map(|s| s.match_mode()) else {
+            return;
+        };
+        for searcher in &mut self.searchers {
+            searcher.set_match_mode(mode.next());
+        }
+        self.requery();
+    }
+    fn toggle_mark(&mut self) {
+        let Some((j, i)) = self.highlighted() else {
+            return;
+        };
+        let handle = self.searchers[j].cached_matches()[i];
+        match self.marks.iter().position(|m| *m == (j, handle)) {
+            Some(idx) => {
+                self.marks.remove(idx);
+            }
+            None => self.marks.push((j, handle)),
+        }
+        let (idx, _) = navigate(KeyCommand::Next, self.cursor, &self.match_counts());
+        self.cursor = idx;
+    }
+    pub fn preview(&self) -> Option<String> {
+        let (j, i) = self.highlighted()?;
+        self.searchers[j].preview(i)
+    }
+
+    /// the groups navigation moves through, a single one when merged
+    fn match_counts(&self) -> Vec<usize> {
+        match self.merged {
+            true => vec![self.rows.len()],
+            false => self
+                .searchers
+                .iter()
+                .map(|s| s.cached_matches().len())
+                .collect(),
+        }
+    }
+    /// Moves the cursor for navigation commands, returns the selection to make for selection
+    /// commands (and picks). Other commands are left to the frontend.
+    pub fn command(&mut self, command: KeyCommand) -> Option<Selection> {
+        match command {
+            KeyCommand::Select => Some(Selection::Primary),
+            KeyCommand::SelectStayOpen => Some(Selection::StayOpen),
+            KeyCommand::SecondaryAction => Some(Selection::Secondary),
+            KeyCommand::Custom(_) => Some(Selection::Primary),
+            KeyCommand::CycleMatchMode => {
+                self.cycle_match_mode();
+                None
+            }
+            KeyCommand::ToggleMark => {
+                if self.multi_select {
+                    self.toggle_mark();
+                }
+                None
+            }
+            KeyCommand::Exit | KeyCommand::TogglePreview | KeyCommand::AcceptCustom => None,
+            command if command.is_line_edit() => None,
+            command => {
+                let (idx, pick) = navigate(command, self.cursor, &self.match_counts());
+                self.cursor = idx;
+                self.pending_cursor = None;
+                pick.then_some(Selection::Primary)
+            }
+        }
+    }
+
+    /// acts on the marked matches, otherwise the highlighted match, or the queery as is when
+    /// nothing matches
+    pub fn select(&mut self, selection: Selection) -> Outcome {
+        if !self.marks.is_empty() && selection != Selection::Secondary {
+            return self.select_marked(selection);
+        }
+        match self.highlighted() {
+            Some((j, i)) => self.select_match(j, i, selection),
+            None => self.select_custom(selection),
+        }
+    }
+    /// acts on the queery as is, through the first module that accepts it
+    pub fn select_custom(&self, selection: Selection) -> Outcome {
+        let queery = &self.queery;
+        match self
+            .searchers
+            .iter()
+            .any(|s| s.module().handle_custom_input(queery))
+        {
+            true => self.after_selection(selection),
+            false => Outcome::Nothing,
+        }
+    }
+    pub fn select_match(&mut self, j: usize, i: usize, selection: Selection) -> Outcome {
+        let Some(searcher) = self.searchers.get(j) else {
+            return Outcome::Nothing;
+        };
+        if i >= searcher.cached_matches().len() {
+            return Outcome::Nothing;
+        }
+        if let Some(completion) = searcher.select(i, selection) {
+            self.set_queery(&completion);
+            self.cursor = 0;
+            return Outcome::Completed(completion);
+        }
+        self.after_selection(selection)
+    }
+    /// acts on each marked match, in the order of their handles (e.g. the order of dmenu options)
+    fn select_marked(&mut self, selection: Selection) -> Outcome {
+        let mut marks = std::mem::take(&mut self.marks);
+        marks.sort_by_key(|(j, handle)| (*j, handle.0));
+        for (j, handle) in marks {
+            self.searchers[j].module().handle_selection(handle);
+        }
+        self.after_selection(selection)
+    }
+    fn after_selection(&self, selection: Selection) -> Outcome {
+        match selection == Selection::StayOpen || self.stay_open {
+            true => Outcome::StayOpen,
+            false => Outcome::Close,
+        }
+    }
+}
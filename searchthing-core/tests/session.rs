@@ -0,0 +1,297 @@
+use searchthing_core::{KeyCommand, Outcome, Selection, Session, WrappedSearcher};
+use searchthing_interface::{MatchInfo, MatchMode, SearchItemHandle, SearchModule, SearcherInfo};
+use std::{
+    cell::{Cell, RefCell},
+    rc::Rc,
+};
+
+type Selected = Rc<RefCell<Vec<(&'static str, Selection)>>>;
+
+/// Matches its items by substring, items ending in '/' complete to themselves, selections are
+/// recorded (custom input as "custom"). Incoming items are added on the next poll. Matches are
+/// counted, including those not returned.
+struct ListModule {
+    name: &'static str,
+    items: Vec<&'static str>,
+    incoming: Vec<&'static str>,
+    selected: Selected,
+    accepts_custom: bool,
+    matched: Cell<usize>,
+}
+
+impl ListModule {
+    fn new(name: &'static str, items: &[&'static str]) -> Self {
+        Self {
+            name,
+            items: items.to_vec(),
+            incoming: vec![],
+            selected: Default::default(),
+            accepts_custom: false,
+            matched: Cell::new(0),
+        }
+    }
+}
+
+impl SearchModule for ListModule {
+    fn mod_info(&self) -> SearcherInfo {
+        SearcherInfo {
+            name: self.name,
+            icon: "",
+        }
+    }
+    fn queery(&self, input: &str, max_returned: u32) -> Vec<SearchItemHandle> {
+        let matches = self.items.iter().enumerate();
+        let matches: Vec<_> = matches
+            .filter(|(_, item)| item.contains(input))
+            .map(|(i, _)| SearchItemHandle(i as i32))
+            .collect();
+        self.matched.set(matches.len());
+        matches.into_iter().take(max_returned as usize).collect()
+    }
+    fn match_info(&self, item: SearchItemHandle) -> MatchInfo {
+        MatchInfo {
+            name: self.items[item.0 as usize],
+            desc: "",
+            icon: "",
+        }
+    }
+    fn handle_selection(&self, selection: SearchItemHandle) {
+        let item = self.items[selection.0 as usize];
+        self.selected.borrow_mut().push((item, Selection::Primary));
+    }
+    fn handle_secondary_selection(&self, selection: SearchItemHandle) {
+        let item = self.items[selection.0 as usize];
+        self.selected
+            .borrow_mut()
+            .push((item, Selection::Secondary));
+    }
+    fn completion(&self, item: SearchItemHandle) -> Option<String> {
+        let item = self.items[item.0 as usize];
+        item.ends_with('/').then(|| item.to_string())
+    }
+    fn poll_updates(&mut self) -> bool {
+        let updated = !self.incoming.is_empty();
+        self.items.append(&mut self.incoming);
+        updated
+    }
+    fn loading(&self) -> bool {
+        !self.incoming.is_empty()
+    }
+    fn item_count(&self) -> Option<usize> {
+        Some(self.items.len())
+    }
+    fn match_count(&self) -> Option<usize> {
+        Some(self.matched.get())
+    }
+    fn set_match_mode(&mut self, _mode: MatchMode) -> bool {
+        true
+    }
+    fn handle_custom_input(&self, _input: &str) -> bool {
+        if self.accepts_custom {
+            self.selected
+                .borrow_mut()
+                .push(("custom", Selection::Primary));
+        }
+        self.accepts_custom
+    }
+}
+
+fn new_session(stay_open: bool) -> (Session, Selected) {
+    let fruit = ListModule::new("Fruit", &["apple", "apricot", "banana"]);
+    let selected = fruit.selected.clone();
+    let dirs = ListModule::new("Dirs", &["a/", "b/"]);
+    let searchers = vec![
+        WrappedSearcher::new(fruit, 10),
+        WrappedSearcher::new(dirs, 10),
+    ];
+    (Session::new(searchers, "", stay_open), selected)
+}
+
+#[test]
+fn navigation_test() {
+    let (mut session, _) = new_session(false);
+    assert_eq!(session.rows().len(), 5);
+    assert_eq!(session.highlighted(), Some((0, 0)));
+    session.command(KeyCommand::NextModule);
+    assert_eq!(session.highlighted(), Some((1, 0)));
+    session.command(KeyCommand::Prev);
+    assert!(session.is_highlighted(0, 2));
+    assert_eq!(session.row_of(1, 1), Some(4));
+
+    // the cursor is kept within the shrinking match list
+    session.set_cursor(4);
+    session.set_queery("ap");
+    assert_eq!(session.rows(), [(0, 0), (0, 1)]);
+    assert_eq!(session.cursor(), 1);
+    session.set_queery("zzz");
+    assert_eq!(session.highlighted(), None);
+    assert_eq!(session.command(KeyCommand::Next), None);
+}
+
+#[test]
+fn selection_test() {
+    let (mut session, selected) = new_session(false);
+    assert_eq!(session.select(Selection::Primary), Outcome::Close);
+    assert_eq!(session.select(Selection::StayOpen), Outcome::StayOpen);
+    assert_eq!(
+        session.select_match(0, 2, Selection::Secondary),
+        Outcome::Close
+    );
+    assert_eq!(
+        *selected.borrow(),
+        [
+            ("apple", Selection::Primary),
+            ("apple", Selection::Primary),
+            ("banana", Selection::Secondary)
+        ]
+    );
+
+    // picks select straight away
+    assert_eq!(
+        session.command(KeyCommand::Pick(3)),
+        Some(Selection::Primary)
+    );
+    assert_eq!(session.highlighted(), Some((1, 0)));
+
+    // completions replace the queery instead
+    assert_eq!(
+        session.select(Selection::Primary),
+        Outcome::Completed("a/".into())
+    );
+    assert_eq!(session.queery(), "a/");
+    assert_eq!(session.rows(), [(1, 0)]);
+    assert_eq!(session.cursor(), 0);
+
+    assert_eq!(
+        session.select_match(0, 9, Selection::Primary),
+        Outcome::Nothing
+    );
+    session.set_queery("zzz");
+    assert_eq!(session.select(Selection::Primary), Outcome::Nothing);
+
+    let (mut session, _) = new_session(true);
+    assert_eq!(session.select(Selection::Primary), Outcome::StayOpen);
+}
+
+#[test]
+fn merged_rows_test() {
+    let (session, _) = new_session(false);
+    let mut session = session.merged(true);
+    session.set_queery("a");
+    // "a/" is the closer match, ahead of the first module's "banana"
+    assert_eq!(session.rows(), [(0, 0), (0, 1), (1, 0), (0, 2)]);
+    session.command(KeyCommand::NextModule);
+    assert_eq!(session.cursor(), 0);
+    session.command(KeyCommand::Last);
+    assert_eq!(session.highlighted(), Some((0, 2)));
+}
+
+#[test]
+fn custom_input_test() {
+    let mut fruit = ListModule::new("Fruit", &["apple", "banana"]);
+    fruit.accepts_custom = true;
+    let selected = fruit.selected.clone();
+    let mut session = Session::new(vec![WrappedSearcher::new(fruit, 10)], "", false);
+    // without matches, the search text is selected as is
+    session.set_queery("zzz");
+    assert_eq!(
+        session.command(KeyCommand::Custom(1)),
+        Some(Selection::Primary)
+    );
+    assert_eq!(session.select(Selection::Primary), Outcome::Close);
+    // or explicitly, over the highlighted match
+    session.set_queery("app");
+    assert_eq!(
+        session.select_custom(Selection::StayOpen),
+        Outcome::StayOpen
+    );
+    assert_eq!(
+        *selected.borrow(),
+        [
+            ("custom", Selection::Primary),
+            ("custom", Selection::Primary)
+        ]
+    );
+    assert_eq!(KeyCommand::Custom(1).exit_code(), 10);
+}
+
+#[test]
+fn multi_select_test() {
+    let (session, selected) = new_session(false);
+    let mut session = session.multi_select(true);
+    session.command(KeyCommand::Next);
+    session.command(KeyCommand::ToggleMark);
+    // marking moves on, and marks are kept while the queery changes
+    assert_eq!(session.highlighted(), Some((0, 2)));
+    session.set_queery("ban");
+    session.command(KeyCommand::ToggleMark);
+    assert!(session.is_marked(0, 0));
+    session.set_queery("");
+    assert!(session.is_marked(0, 1) && session.is_marked(0, 2));
+    assert_eq!(session.mark_count(), 2);
+    session.set_cursor(0);
+    session.command(KeyCommand::ToggleMark);
+    session.set_cursor(0);
+    session.command(KeyCommand::ToggleMark);
+    assert!(!session.is_marked(0, 0));
+
+    // all are selected, in the order of the options rather than of marking
+    assert_eq!(session.select(Selection::Primary), Outcome::Close);
+    assert_eq!(
+        *selected.borrow(),
+        [
+            ("apricot", Selection::Primary),
+            ("banana", Selection::Primary)
+        ]
+    );
+    assert_eq!(session.mark_count(), 0);
+
+    let (mut session, _) = new_session(false);
+    session.command(KeyCommand::ToggleMark);
+    assert_eq!(session.mark_count(), 0);
+}
+
+#[test]
+fn streaming_test() {
+    let mut fruit = ListModule::new("Fruit", &["apple"]);
+    fruit.incoming = vec!["apricot", "banana"];
+    let mut session = Session::new(vec![WrappedSearcher::new(fruit, 10)], "a", false);
+    // highlighted once it has loaded
+    session.set_cursor(2);
+    assert_eq!(session.cursor(), 0);
+    assert!(session.loading());
+    assert_eq!(
+        session.searchers()[0].status().as_deref(),
+        Some("1/1 loading…")
+    );
+
+    assert!(session.poll_updates());
+    assert_eq!(session.rows().len(), 3);
+    assert_eq!(session.cursor(), 2);
+    assert!(!session.loading());
+    assert_eq!(session.searchers()[0].status().as_deref(), Some("3/3"));
+    assert!(!session.poll_updates());
+}
+
+#[test]
+fn match_count_test() {
+    let fruit = ListModule::new("Fruit", &["apple", "apricot", "banana"]);
+    let session = Session::new(vec![WrappedSearcher::new(fruit, 1)], "a", false);
+    assert_eq!(session.rows().len(), 1);
+    // all matches counted, not only those shown
+    assert_eq!(session.searchers()[0].status().as_deref(), Some("3/3"));
+}
+
+#[test]
+fn match_mode_test() {
+    let (mut session, _) = new_session(false);
+    assert_eq!(session.searchers()[0].status().as_deref(), Some("3/3"));
+    session.command(KeyCommand::CycleMatchMode);
+    for searcher in session.searchers() {
+        assert_eq!(searcher.match_mode(), Some(MatchMode::Prefix));
+    }
+    assert_eq!(
+        session.searchers()[1].status().as_deref(),
+        Some("prefix 2/2")
+    );
+}
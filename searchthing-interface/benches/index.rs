@@ -0,0 +1,62 @@
+//! Latency of (filtered) searches of a [SearchIndex] made on every keystroke as queeries are typed
+//! out, with `cargo bench -p searchthing-interface --bench index`
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use searchthing_interface::{MatchMode, SearchIndex};
+use std::hint::black_box;
+
+static WORDS: &[&str] = &[
+    "fire", "fox", "code", "studio", "term", "files", "mail", "chat", "music", "video", "image",
+    "edit", "view", "web", "net", "game",
+];
+
+static QUEERIES: &[&str] = &["firefox", "code edit", "zzzq"];
+
+/// items named like applications, e.g. "Firefox Web 12" described as "web fire fox tool"
+fn index() -> SearchIndex {
+    let mut index = SearchIndex::default();
+    for (i, a) in WORDS.iter().enumerate() {
+        for b in WORDS {
+            for c in WORDS {
+                for n in 0..8 {
+                    let name = format!("{a}{b} {c} {n}");
+                    let desc = format!("{c} {a} {b} tool, {}", WORDS[(i + n) % WORDS.len()]);
+                    index.push([name, desc]);
+                }
+            }
+        }
+    }
+    index
+}
+
+/// types out the queery, starting over each time as the first char does not narrow down the last
+fn type_out(queery: &str, mut search: impl FnMut(&str)) {
+    for (end, _) in queery.char_indices().skip(1) {
+        search(black_box(&queery[..end]));
+    }
+    search(black_box(queery));
+}
+
+fn search(c: &mut Criterion) {
+    let index = index();
+    for mode in [MatchMode::Fuzzy, MatchMode::Substring] {
+        let mut group = c.benchmark_group(format!("index/{}", mode.name()));
+        for queery in QUEERIES {
+            group.bench_with_input(BenchmarkId::new("all", queery), queery, |b, queery| {
+                b.iter(|| type_out(queery, |q| drop(index.search(q, mode, 50))))
+            });
+            // NOTE: e.g. applications in a category
+            group.bench_with_input(BenchmarkId::new("filtered", queery), queery, |b, queery| {
+                b.iter(|| {
+                    type_out(queery, |q| {
+                        drop(index.search_filtered(q, mode, 50, |idx| idx % 7 == 0))
+                    })
+                })
+            });
+        }
+        group.finish();
+    }
+}
+
+criterion_group!(benches, search);
+criterion_main!(benches);
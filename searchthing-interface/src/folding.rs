@@ -0,0 +1,94 @@
+//! Folding of text for matching, so that e.g. "cafe" finds "Café": compatibility decomposition
+//! (NFKD), dropping the combining marks it splits off (diacritics), then lowercasing.
+
+use unicode_normalization::{char::is_combining_mark, UnicodeNormalization};
+
+/// the folded form of a char, possibly several chars (e.g. "fi" for the "ﬁ" ligature) or none
+fn fold_char(c: char) -> impl Iterator<Item = char> {
+    c.nfkd()
+        .flat_map(char::to_lowercase)
+        .filter(|c| !is_combining_mark(*c))
+}
+
+/// Folds text for matching, e.g. item text when it is loaded, and queeries
+pub fn fold(text: &str) -> String {
+    text.chars().flat_map(fold_char).collect()
+}
+
+/// [fold]s the literal chars of a regular expression, so that e.g. "Café" matches folded text,
+/// leaving each escaped char as is, as folding could change its meaning (e.g. of `\D`). Neither
+/// are the flags and names of groups like `(?U)` or `(?P<Name>…)` folded, and chars that fold to
+/// metacharacters are escaped (e.g. the fullwidth "＊" to `\*`).
+pub(crate) fn fold_regex(pattern: &str) -> String {
+    let mut folded = String::with_capacity(pattern.len());
+    let mut chars = pattern.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => folded.extend([c].into_iter().chain(chars.next())),
+            '(' if chars.peek() == Some(&'?') => {
+                folded.push(c);
+                for c in chars.by_ref() {
+                    folded.push(c);
+                    if matches!(c, ')' | ':' | '>') {
+                        break;
+                    }
+                }
+            }
+            // NOTE: ASCII just lowercases, leaving metacharacters as they are
+            c if !c.is_ascii() => folded.push_str(&regex::escape(&fold(&c.to_string()))),
+            c => folded.push(c.to_ascii_lowercase()),
+        }
+    }
+    folded
+}
+
+/// Folded text, along with where each of its chars came from in the original, to map matched
+/// indices back for highlighting.
+pub struct FoldedText {
+    text: String,
+    /// the original char index of each folded char
+    origins: Vec<usize>,
+}
+
+impl FoldedText {
+    pub fn new(original: &str) -> Self {
+        let mut text = String::with_capacity(original.len());
+        let mut origins = Vec::with_capacity(original.len());
+        for (c, ch) in original.chars().enumerate() {
+            for folded in fold_char(ch) {
+                text.push(folded);
+                origins.push(c);
+            }
+        }
+        Self { text, origins }
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.text
+    }
+
+    /// the original char indices of folded char indices, in order and without repeats
+    pub fn original_idxs(&self, idxs: &[usize]) -> Vec<usize> {
+        let mut original: Vec<_> = idxs
+            .iter()
+            .filter_map(|i| self.origins.get(*i).copied())
+            .collect();
+        original.sort_unstable();
+        original.dedup();
+        original
+    }
+}
+
+#[test]
+fn folding_test() {
+    assert_eq!(fold("Café Ǆ ﬁle"), "cafe dz file");
+    assert_eq!(fold("İstanbul"), "istanbul");
+    assert_eq!(fold_regex(r"É.*\D\\Ä"), r"e.*\D\\a");
+    assert_eq!(fold_regex("(?U)Ä＊？．"), r"(?U)a\*\?\.");
+    assert_eq!(fold_regex("(?P<Ä>É)|(?i:Ö)"), "(?P<Ä>e)|(?i:o)");
+    // the ligature folds to two chars, both from the second of the original
+    let folded = FoldedText::new("Ωﬁé");
+    assert_eq!(folded.as_str(), "ωfie");
+    assert_eq!(folded.original_idxs(&[1, 2, 3]), vec![1, 2]);
+    assert_eq!(folded.original_idxs(&[9]), vec![]);
+}
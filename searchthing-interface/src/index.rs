@@ -0,0 +1,260 @@
+//! [SearchIndex], for matching the items of modules with many of them (e.g. the ~35k symbols)
+//! quickly enough to do so on every keystroke.
+
+use crate::{fold, MatchMode, SearchItemHandle};
+use rayon::prelude::*;
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Mutex,
+};
+
+/// Items are matched in parallel in chunks of at least this many, so that few are not split up
+const MIN_CHUNK: usize = 1024;
+
+/// The chars of some text, approximately (as 64 bits, some chars sharing one), to cheaply rule out
+/// items that lack some char of the queery before matching them properly
+#[derive(Clone, Copy, Default, PartialEq, Debug)]
+struct CharSet(u64);
+
+impl CharSet {
+    /// whitespace is left out, as matching may skip over it
+    fn new(text: &str) -> Self {
+        let bits = text
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .fold(0, |bits, c| bits | Self::bit(c));
+        Self(bits)
+    }
+
+    fn bit(c: char) -> u64 {
+        let n = match c {
+            'a'..='z' => c as u32 - 'a' as u32,
+            '0'..='9' => 26 + c as u32 - '0' as u32,
+            _ => 36 + c as u32 % 28,
+        };
+        1 << n
+    }
+
+    fn union(self, other: Self) -> Self {
+        Self(self.0 | other.0)
+    }
+
+    fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
+
+struct IndexEntry {
+    texts: Box<[String]>,
+    chars: CharSet,
+}
+
+/// All the items that matched the last search (before filtering), to narrow down as it is typed
+/// out
+struct LastMatches {
+    /// folded
+    queery: String,
+    mode: MatchMode,
+    /// the number of items then, later ones have not been matched yet
+    searched: usize,
+    idxs: Vec<usize>,
+}
+
+impl LastMatches {
+    /// the items that can match the (folded) queery, if it only narrows down the last one
+    fn candidates(&self, queery: &str, mode: MatchMode, len: usize) -> Option<Vec<usize>> {
+        // NOTE: not so for whole words, regular expressions or extended syntax, e.g. "vi" is not a
+        // whole word in "vim" whereas "vim" is
+        let narrows = matches!(
+            mode,
+            MatchMode::Fuzzy | MatchMode::Prefix | MatchMode::Substring | MatchMode::Initials
+        );
+        (narrows && mode == self.mode && queery.starts_with(&self.queery)).then(|| {
+            let mut idxs = self.idxs.clone();
+            idxs.extend(self.searched..len);
+            idxs
+        })
+    }
+}
+
+/// The [fold]ed search texts (e.g. name and description) of a module's items, which are matched
+/// in parallel, only keeping the best ones. Items are referred to by their position, as
+/// [SearchItemHandle]s.
+///
+/// As a queery is typed out, each search only matches the items that matched the one before,
+/// starting over when it is not just extended (e.g. on deletion).
+#[derive(Default)]
+pub struct SearchIndex {
+    entries: Vec<IndexEntry>,
+    last_matches: Mutex<Option<LastMatches>>,
+    /// of the last search, after filtering, before keeping the best
+    match_count: AtomicUsize,
+}
+
+impl SearchIndex {
+    /// adds an item with the given search texts, empty ones being left out
+    pub fn push<S: AsRef<str>>(&mut self, texts: impl IntoIterator<Item = S>) {
+        let texts: Box<[String]> = texts
+            .into_iter()
+            .map(|t| fold(t.as_ref()))
+            .filter(|t| !t.is_empty())
+            .collect();
+        let chars = texts
+            .iter()
+            .fold(CharSet::default(), |chars, t| chars.union(CharSet::new(t)));
+        self.entries.push(IndexEntry { texts, chars });
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// the number of items that matched the last search, which may be more than it returned
+    pub fn match_count(&self) -> usize {
+        self.match_count.load(Ordering::Relaxed)
+    }
+
+    /// the best matches of the queery, best first (and in order when scored equally), scored by
+    /// the best matching text of each item
+    pub fn search(
+        &self,
+        queery: &str,
+        mode: MatchMode,
+        max_returned: usize,
+    ) -> Vec<SearchItemHandle> {
+        self.search_filtered(queery, mode, max_returned, |_| true)
+    }
+
+    /// as [SearchIndex::search], among the items (by position) that pass the filter, which is
+    /// applied to the matches, so that they still narrow down when it changes (e.g. a category)
+    pub fn search_filtered(
+        &self,
+        queery: &str,
+        mode: MatchMode,
+        max_returned: usize,
+        filter: impl Fn(usize) -> bool,
+    ) -> Vec<SearchItemHandle> {
+        let queery = mode.fold_queery(queery);
+        let mut last = self.last_matches.lock().unwrap();
+        let candidates = last
+            .as_ref()
+            .and_then(|last| last.candidates(&queery, mode, self.len()));
+        let mut matches = match candidates {
+            Some(idxs) => self.matches(idxs.into_par_iter(), &queery, mode),
+            None => self.matches((0..self.len()).into_par_iter(), &queery, mode),
+        };
+        *last = Some(LastMatches {
+            idxs: matches.iter().map(|(_, idx)| *idx).collect(),
+            queery,
+            mode,
+            searched: self.len(),
+        });
+        drop(last);
+        matches.retain(|(_, idx)| filter(*idx));
+        self.match_count.store(matches.len(), Ordering::Relaxed);
+        best_first(&mut matches, max_returned);
+        handles(matches)
+    }
+
+    /// the scores of the candidates (by position) that match, in the same order
+    fn matches(
+        &self,
+        candidates: impl IndexedParallelIterator<Item = usize>,
+        queery: &str,
+        mode: MatchMode,
+    ) -> Vec<(i64, usize)> {
+        // NOTE: the chars of a regular expression or extended search syntax need not all match
+        let required = match mode {
+            MatchMode::Regex | MatchMode::Extended => CharSet::default(),
+            _ => CharSet::new(queery),
+        };
+        candidates
+            .with_min_len(MIN_CHUNK)
+            .filter_map(|idx| {
+                let entry = &self.entries[idx];
+                if !entry.chars.contains(required) {
+                    return None;
+                }
+                let texts = entry.texts.iter();
+                let score = texts.filter_map(|t| mode.score(t, queery)).max()?;
+                Some((score, idx))
+            })
+            .collect()
+    }
+}
+
+fn handles(matches: Vec<(i64, usize)>) -> Vec<SearchItemHandle> {
+    matches
+        .into_iter()
+        .map(|(_, idx)| SearchItemHandle(idx as i32))
+        .collect()
+}
+
+/// sorts the k best of the scored items, dropping the rest unsorted
+fn best_first(matches: &mut Vec<(i64, usize)>, k: usize) {
+    let order = |a: &(i64, usize), b: &(i64, usize)| b.0.cmp(&a.0).then(a.1.cmp(&b.1));
+    if k < matches.len() {
+        matches.select_nth_unstable_by(k, order);
+        matches.truncate(k);
+    }
+    matches.sort_unstable_by(order);
+}
+
+#[test]
+fn search_index_test() {
+    let mut index = SearchIndex::default();
+    index.push(["Café", "coffee shop"]);
+    index.push(["cafeteria", ""]);
+    index.push(["tea", "leaves"]);
+    index.push(["Cafe", ""]);
+    assert!(CharSet::new("ab c").contains(CharSet::new("ca")));
+    assert!(!CharSet::new("abc").contains(CharSet::new("cd")));
+    let handles = |queery: &str, mode: MatchMode, max: usize| {
+        let handles = index.search(queery, mode, max);
+        handles.into_iter().map(|h| h.0).collect::<Vec<_>>()
+    };
+    // equal scores keep their order
+    assert_eq!(handles("cafe", MatchMode::Prefix, 10), vec![0, 3, 1]);
+    assert_eq!(handles("cafe", MatchMode::Prefix, 2), vec![0, 3]);
+    // all matches are counted, not just those returned
+    assert_eq!(index.match_count(), 3);
+    assert_eq!(handles("shop", MatchMode::Substring, 10), vec![0]);
+    assert_eq!(handles("", MatchMode::Fuzzy, 10), vec![0, 1, 2, 3]);
+    assert_eq!(handles("^tea !cafe", MatchMode::Extended, 10), vec![2]);
+    let filtered = index.search_filtered("e", MatchMode::Substring, 10, |idx| idx != 2);
+    assert_eq!(filtered.len(), 3);
+    assert_eq!(index.match_count(), 3);
+}
+
+#[test]
+fn narrowing_test() {
+    let mut index = SearchIndex::default();
+    index.push(["Café"]);
+    index.push(["cafeteria"]);
+    index.push(["tea"]);
+    let search = |index: &SearchIndex, queery: &str, mode: MatchMode| {
+        let handles = index.search(queery, mode, 10);
+        handles.into_iter().map(|h| h.0).collect::<Vec<_>>()
+    };
+    assert_eq!(search(&index, "c", MatchMode::Substring), vec![0, 1]);
+    // items added since are matched too
+    index.push(["Cafés"]);
+    assert_eq!(search(&index, "caf", MatchMode::Substring), vec![0, 1, 3]);
+    assert_eq!(search(&index, "cafet", MatchMode::Substring), vec![1]);
+    // starting over on deletion, or when switching to a mode that does not narrow down
+    assert_eq!(search(&index, "caf", MatchMode::Substring), vec![0, 1, 3]);
+    assert_eq!(search(&index, "te", MatchMode::Exact), vec![]);
+    assert_eq!(search(&index, "tea", MatchMode::Exact), vec![2]);
+    // narrowed down before filtering, so that a different filter still gets all the matches
+    let filtered = |filter: fn(usize) -> bool| {
+        let handles = index.search_filtered("cafe", MatchMode::Prefix, 10, filter);
+        handles.into_iter().map(|h| h.0).collect::<Vec<_>>()
+    };
+    assert_eq!(search(&index, "caf", MatchMode::Prefix), vec![0, 3, 1]);
+    assert_eq!(filtered(|idx| idx == 1), vec![1]);
+    assert_eq!(filtered(|idx| idx != 1), vec![0, 3]);
+}
@@ -1,7 +1,19 @@
-use std::ops::Range;
+use std::{ops::Range, sync::LazyLock};
 
 use fuzzy_matcher::{skim::SkimMatcherV2, FuzzyMatcher};
 
+mod folding;
+mod index;
+mod matching;
+
+pub use folding::{fold, FoldedText};
+pub use index::SearchIndex;
+
+use matching::char_idxs;
+pub use matching::{
+    ExactSearch, ExtendedSearch, InitialsSearch, MatchMode, PrefixSearch, RegexSearch,
+};
+
 #[repr(transparent)]
 #[derive(Clone, Copy, PartialEq, Debug)]
 pub struct SearchItemHandle(pub i32);
@@ -32,11 +44,64 @@ pub trait SearchModule {
 
     // NOTE: the word handle is used with two different meanings here
     fn handle_selection(&self, selection: SearchItemHandle);
+
+    /// Optional, if this returns some text for a selected item, the search input is replaced with
+    /// it (e.g. to drill down into a category) instead of calling [SearchModule::handle_selection].
+    fn completion(&self, _item: SearchItemHandle) -> Option<String> {
+        None
+    }
+
+    /// Optional, an alternative action on selection (e.g. bound to Shift+Enter), does nothing by
+    /// default.
+    fn handle_secondary_selection(&self, _selection: SearchItemHandle) {}
+
+    /// Optional, acts on search text that is selected as is (e.g. when it matches nothing),
+    /// returns whether it did anything with it. Does nothing by default.
+    fn handle_custom_input(&self, _input: &str) -> bool {
+        false
+    }
+
+    /// Optional, longer details of an item, shown in the preview panel.
+    fn preview(&self, _item: SearchItemHandle) -> Option<String> {
+        None
+    }
+
+    /// Optional, for modules whose items arrive over time (e.g. read from a pipe): takes in any
+    /// new items, returns whether there were some, so that the last queery is rerun. Existing
+    /// handles should stay valid.
+    fn poll_updates(&mut self) -> bool {
+        false
+    }
+
+    /// Optional, whether more items may still arrive, so that frontends keep polling for them and
+    /// show that the module is loading.
+    fn loading(&self) -> bool {
+        false
+    }
+
+    /// Optional, the total number of items, shown along with the number of matches.
+    fn item_count(&self) -> Option<usize> {
+        None
+    }
+
+    /// Optional, the number of items the last queery matched, when it returned only the best of
+    /// them. Otherwise the number returned is shown.
+    fn match_count(&self) -> Option<usize> {
+        None
+    }
+
+    /// Optional, switches how items are matched, returns whether the module supports it (and so
+    /// [MatchMode]s are offered for it). Matching is fuzzy otherwise.
+    fn set_match_mode(&mut self, _mode: MatchMode) -> bool {
+        false
+    }
 }
 
-/// second argument is changed to lowercase within the function, first is not
+/// second argument is changed to lowercase within the function, first is not, the range is that
+/// of the lowercased substring in the first
 pub fn substring_range(string: &str, substring: &str) -> Option<Range<usize>> {
-    let start = string.find(&substring.to_lowercase());
+    let substring = substring.to_lowercase();
+    let start = string.find(&substring);
     start.map(|si| si..si + substring.len())
 }
 
@@ -47,20 +112,31 @@ pub trait SearchMethod {
     fn match_idxs(search_text: &str, queery_text: &str) -> Option<(i64, Vec<usize>)>;
 }
 
-#[allow(dead_code)]
+/// a substring, earlier ones scoring higher
 pub struct BasicSearch;
 
 impl SearchMethod for BasicSearch {
     fn match_idxs(search_text: &str, queery_text: &str) -> Option<(i64, Vec<usize>)> {
-        substring_range(search_text, queery_text).map(|r| (-(r.start as i64), r.collect()))
+        substring_range(search_text, queery_text)
+            .map(|r| (-(r.start as i64), char_idxs(search_text, r)))
     }
 }
 
 pub struct FuzzySearch;
 
+/// shared rather than created per match, as it caches its buffers (per thread)
+static FUZZY_MATCHER: LazyLock<SkimMatcherV2> = LazyLock::new(SkimMatcherV2::default);
+
+impl FuzzySearch {
+    /// the score alone, which is quicker to find than the indices
+    pub fn score(search_text: &str, queery_text: &str) -> Option<i64> {
+        FUZZY_MATCHER.fuzzy_match(search_text, queery_text)
+    }
+}
+
 impl SearchMethod for FuzzySearch {
     fn match_idxs(search_text: &str, queery_text: &str) -> Option<(i64, Vec<usize>)> {
-        SkimMatcherV2::default().fuzzy_indices(search_text, queery_text)
+        FUZZY_MATCHER.fuzzy_indices(search_text, queery_text)
     }
 }
 
@@ -0,0 +1,327 @@
+//! Matching algorithms besides [FuzzySearch], and [MatchMode] to choose between them at runtime.
+//! Search text is expected in lowercase (or [fold]ed), queeries are lowercased here. Matched
+//! indices are char indices, as with [FuzzySearch].
+
+use crate::{fold, folding::fold_regex, BasicSearch, FoldedText, FuzzySearch, SearchMethod};
+use regex::{Regex, RegexBuilder};
+use std::{cell::RefCell, ops::Range};
+
+/// char indices of a byte range of the text
+pub(crate) fn char_idxs(text: &str, range: Range<usize>) -> Vec<usize> {
+    text.char_indices()
+        .enumerate()
+        .filter(|(_, (i, _))| range.contains(i))
+        .map(|(c, _)| c)
+        .collect()
+}
+
+/// the start of the text, shorter texts scoring higher
+pub struct PrefixSearch;
+
+impl SearchMethod for PrefixSearch {
+    fn match_idxs(search_text: &str, queery_text: &str) -> Option<(i64, Vec<usize>)> {
+        let queery = queery_text.to_lowercase();
+        search_text.starts_with(&queery).then(|| {
+            let score = -(search_text.chars().count() as i64);
+            (score, char_idxs(search_text, 0..queery.len()))
+        })
+    }
+}
+
+/// the queery as whole words, e.g. "code" in "visual studio code" but not in "vscodium"
+pub struct ExactSearch;
+
+impl SearchMethod for ExactSearch {
+    fn match_idxs(search_text: &str, queery_text: &str) -> Option<(i64, Vec<usize>)> {
+        let queery = queery_text.to_lowercase();
+        if queery.is_empty() {
+            return Some((0, vec![]));
+        }
+        let is_boundary = |c: Option<char>| c.is_none_or(|c| !c.is_alphanumeric());
+        search_text
+            .match_indices(&queery)
+            .map(|(start, _)| start..start + queery.len())
+            .find(|r| {
+                is_boundary(search_text[..r.start].chars().next_back())
+                    && is_boundary(search_text[r.end..].chars().next())
+            })
+            .map(|r| (-(r.start as i64), char_idxs(search_text, r)))
+    }
+}
+
+/// a case insensitive regular expression, matching nothing while it is invalid (e.g. half typed)
+pub struct RegexSearch;
+
+thread_local! {
+    /// the last queery, compiled, as the same one is matched against every item
+    static LAST_REGEX: RefCell<Option<(String, Option<Regex>)>> = const { RefCell::new(None) };
+}
+
+impl SearchMethod for RegexSearch {
+    fn match_idxs(search_text: &str, queery_text: &str) -> Option<(i64, Vec<usize>)> {
+        LAST_REGEX.with_borrow_mut(|last| {
+            if last.as_ref().is_none_or(|(q, _)| q != queery_text) {
+                let regex = RegexBuilder::new(queery_text)
+                    .case_insensitive(true)
+                    .build()
+                    .ok();
+                *last = Some((queery_text.into(), regex));
+            }
+            let mtch = last.as_ref()?.1.as_ref()?.find(search_text)?;
+            Some((-(mtch.start() as i64), char_idxs(search_text, mtch.range())))
+        })
+    }
+}
+
+/// the first letters of words in order, e.g. "gc" for "google chrome", fewer skipped words
+/// scoring higher
+pub struct InitialsSearch;
+
+impl SearchMethod for InitialsSearch {
+    fn match_idxs(search_text: &str, queery_text: &str) -> Option<(i64, Vec<usize>)> {
+        let queery: Vec<char> = queery_text.to_lowercase().chars().collect();
+        let mut initials = vec![];
+        let mut prev_alphanumeric = false;
+        for (c, ch) in search_text.chars().enumerate() {
+            if ch.is_alphanumeric() && !prev_alphanumeric {
+                initials.push((c, ch));
+            }
+            prev_alphanumeric = ch.is_alphanumeric();
+        }
+        let mut idxs = vec![];
+        let mut skipped = 0;
+        let mut remaining = initials.into_iter();
+        for q in queery.iter().filter(|q| !q.is_whitespace()) {
+            loop {
+                let (c, ch) = remaining.next()?;
+                if ch == *q {
+                    idxs.push(c);
+                    break;
+                }
+                skipped += 1;
+            }
+        }
+        Some((-skipped, idxs))
+    }
+}
+
+/// fzf's extended search syntax, space separated terms that all have to match: fuzzy by default,
+/// `'exact` for a substring, `^prefix`, `suffix$`, and `!term` for a substring (or `!^prefix`,
+/// `!suffix$`) that must not match
+pub struct ExtendedSearch;
+
+impl ExtendedSearch {
+    fn match_term(search_text: &str, term: &str) -> Option<(i64, Vec<usize>)> {
+        if let Some(exact) = term.strip_prefix('\'') {
+            return BasicSearch::match_idxs(search_text, exact);
+        }
+        let (term, prefix) = match term.strip_prefix('^') {
+            Some(term) => (term, true),
+            None => (term, false),
+        };
+        let (term, suffix) = match term.strip_suffix('$') {
+            Some(term) if !term.is_empty() => (term, true),
+            _ => (term, false),
+        };
+        let term = term.to_lowercase();
+        let range = match (prefix, suffix) {
+            (false, false) => return FuzzySearch::match_idxs(search_text, &term),
+            (true, true) => (search_text == term).then_some(0..term.len()),
+            (true, false) => search_text.starts_with(&term).then_some(0..term.len()),
+            (false, true) => search_text
+                .ends_with(&term)
+                .then(|| search_text.len() - term.len()..search_text.len()),
+        }?;
+        Some((0, char_idxs(search_text, range)))
+    }
+}
+
+impl SearchMethod for ExtendedSearch {
+    fn match_idxs(search_text: &str, queery_text: &str) -> Option<(i64, Vec<usize>)> {
+        let mut score = 0;
+        let mut idxs = vec![];
+        for term in queery_text.split_whitespace() {
+            match term.strip_prefix('!').filter(|t| !t.is_empty()) {
+                Some(negated) => {
+                    // NOTE: a substring unless marked otherwise, as fuzzily many unrelated
+                    // items would match
+                    let marked = negated.starts_with(['^', '\'']) || negated.ends_with('$');
+                    let matched = match marked {
+                        true => Self::match_term(search_text, negated),
+                        false => BasicSearch::match_idxs(search_text, negated),
+                    };
+                    if matched.is_some() {
+                        return None;
+                    }
+                }
+                None => {
+                    let (s, term_idxs) = Self::match_term(search_text, term)?;
+                    score += s;
+                    idxs.extend(term_idxs);
+                }
+            }
+        }
+        idxs.sort_unstable();
+        idxs.dedup();
+        Some((score, idxs))
+    }
+}
+
+/// How modules match their items against the queery, chosen in the config file or cycled
+/// through with a keybinding
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum MatchMode {
+    #[default]
+    Fuzzy,
+    Prefix,
+    Substring,
+    Exact,
+    Regex,
+    Initials,
+    Extended,
+}
+
+impl MatchMode {
+    /// in the order cycled through
+    pub const ALL: [Self; 7] = [
+        Self::Fuzzy,
+        Self::Prefix,
+        Self::Substring,
+        Self::Exact,
+        Self::Regex,
+        Self::Initials,
+        Self::Extended,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Fuzzy => "fuzzy",
+            Self::Prefix => "prefix",
+            Self::Substring => "substring",
+            Self::Exact => "exact",
+            Self::Regex => "regex",
+            Self::Initials => "initials",
+            Self::Extended => "extended",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|m| m.name() == name)
+    }
+
+    pub fn next(&self) -> Self {
+        let idx = Self::ALL.iter().position(|m| m == self).unwrap_or(0);
+        Self::ALL[(idx + 1) % Self::ALL.len()]
+    }
+
+    /// [fold]s a queery, to match [fold]ed text with, only the literal chars of a regular
+    /// expression (see [fold_regex])
+    pub fn fold_queery(&self, queery: &str) -> String {
+        match self {
+            Self::Regex => fold_regex(queery),
+            _ => fold(queery),
+        }
+    }
+
+    /// matches text against a queery folded with [MatchMode::fold_queery], returns the matched
+    /// char indices of the original text
+    pub fn match_folded(&self, text: &FoldedText, queery: &str) -> Option<(i64, Vec<usize>)> {
+        let (score, idxs) = self.match_idxs(text.as_str(), queery)?;
+        Some((score, text.original_idxs(&idxs)))
+    }
+
+    /// the score alone of [MatchMode::match_idxs]
+    pub fn score(&self, search_text: &str, queery_text: &str) -> Option<i64> {
+        match self {
+            Self::Fuzzy => FuzzySearch::score(search_text, queery_text),
+            _ => self.match_idxs(search_text, queery_text).map(|(s, _)| s),
+        }
+    }
+
+    /// as [SearchMethod::match_idxs], with the method of this mode
+    pub fn match_idxs(&self, search_text: &str, queery_text: &str) -> Option<(i64, Vec<usize>)> {
+        match self {
+            Self::Fuzzy => FuzzySearch::match_idxs(search_text, queery_text),
+            Self::Prefix => PrefixSearch::match_idxs(search_text, queery_text),
+            Self::Substring => BasicSearch::match_idxs(search_text, queery_text),
+            Self::Exact => ExactSearch::match_idxs(search_text, queery_text),
+            Self::Regex => RegexSearch::match_idxs(search_text, queery_text),
+            Self::Initials => InitialsSearch::match_idxs(search_text, queery_text),
+            Self::Extended => ExtendedSearch::match_idxs(search_text, queery_text),
+        }
+    }
+}
+
+#[test]
+fn match_mode_test() {
+    let idxs =
+        |mode: MatchMode, text: &str, queery: &str| mode.match_idxs(text, queery).map(|m| m.1);
+    assert_eq!(
+        idxs(MatchMode::Prefix, "firefox", "Fir"),
+        Some(vec![0, 1, 2])
+    );
+    assert_eq!(idxs(MatchMode::Prefix, "firefox", "fox"), None);
+    assert_eq!(idxs(MatchMode::Substring, "éclair", "cl"), Some(vec![1, 2]));
+    assert_eq!(idxs(MatchMode::Exact, "vim", "vi"), None);
+    assert_eq!(idxs(MatchMode::Exact, "vscodium", "code"), None);
+    assert_eq!(
+        idxs(MatchMode::Exact, "studio code", "code"),
+        Some(vec![7, 8, 9, 10])
+    );
+    assert_eq!(
+        idxs(MatchMode::Regex, "gimp 2.10", r"\d+\.\d"),
+        Some(vec![5, 6, 7])
+    );
+    assert_eq!(idxs(MatchMode::Regex, "gimp", "(unclosed"), None);
+    assert_eq!(
+        idxs(MatchMode::Initials, "google chrome", "gc"),
+        Some(vec![0, 7])
+    );
+    assert_eq!(
+        idxs(MatchMode::Initials, "visual studio-code", "vc"),
+        Some(vec![0, 14])
+    );
+    assert_eq!(idxs(MatchMode::Initials, "google chrome", "cg"), None);
+    assert_eq!(
+        idxs(
+            MatchMode::Extended,
+            "firefox web browser",
+            "^fire 'brow !chrome"
+        ),
+        Some(vec![0, 1, 2, 3, 12, 13, 14, 15])
+    );
+    assert_eq!(idxs(MatchMode::Extended, "firefox", "fx !^fire"), None);
+    // a negated term is a substring, "fx" being a subsequence of "firefox" but not in it
+    assert_eq!(
+        idxs(MatchMode::Extended, "firefox", "fo !fx"),
+        Some(vec![0, 5])
+    );
+    assert_eq!(idxs(MatchMode::Extended, "firefox", "fo !fox$"), None);
+    assert_eq!(
+        idxs(MatchMode::Extended, "firefox", "fox$"),
+        Some(vec![4, 5, 6])
+    );
+    assert_eq!(MatchMode::Extended.next(), MatchMode::Fuzzy);
+    let queery = MatchMode::Substring.fold_queery("CAFE");
+    let text = FoldedText::new("Le Café");
+    assert_eq!(
+        MatchMode::Substring.match_folded(&text, &queery),
+        Some((-3, vec![3, 4, 5, 6]))
+    );
+    // the literals of a regular expression are folded, but not its escapes
+    let regex = |queery: &str| {
+        let queery = MatchMode::Regex.fold_queery(queery);
+        MatchMode::Regex.match_folded(&text, &queery).map(|m| m.1)
+    };
+    assert_eq!(regex("Café"), Some(vec![3, 4, 5, 6]));
+    assert_eq!(regex("É$"), Some(vec![6]));
+    assert_eq!(regex(r"\Scafe"), None);
+    // a fullwidth "．" stays a literal dot rather than matching any char
+    let text = FoldedText::new("v1x2 v1.2");
+    let queery = MatchMode::Regex.fold_queery("1．2");
+    assert_eq!(
+        MatchMode::Regex.match_folded(&text, &queery).map(|m| m.1),
+        Some(vec![6, 7, 8])
+    );
+    assert_eq!(MatchMode::from_name("regex"), Some(MatchMode::Regex));
+}
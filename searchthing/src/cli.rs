@@ -0,0 +1,130 @@
+//! Headless `query` and `select` commands, for scripting and testing without a display, e.g.
+//!
+//! ```sh
+//! searchthing query firefox --json
+//! searchthing select applications:42
+//! ```
+//!
+//! Matches are identified by module key and item handle, which are stable between runs as long as
+//! the module's items do not change.
+
+use crate::Command;
+use searchthing_core::{best_match, Session, WrappedSearcher};
+use searchthing_interface::{MatchInfo, SearchItemHandle};
+use serde::Serialize;
+use std::{thread::sleep, time::Duration};
+
+#[derive(Serialize)]
+struct QueryResult<'a> {
+    module: &'a str,
+    id: String,
+    name: &'a str,
+    desc: &'a str,
+    icon: &'a str,
+    /// score of the better matching of the name and description, if either match
+    score: Option<i64>,
+}
+
+pub fn run(command: Command, mut searchers: Vec<WrappedSearcher>) -> Result<(), String> {
+    // NOTE: e.g. dmenu options are read from stdin in the background
+    for searcher in &mut searchers {
+        while searcher.module().loading() {
+            if !searcher.module_mut().poll_updates() {
+                sleep(Duration::from_millis(10));
+            }
+        }
+    }
+    match command {
+        Command::Query { text, module, json } => query(searchers, &text, module.as_deref(), json),
+        Command::Select { id } => select(&searchers, &id),
+    }
+}
+
+fn query(
+    mut searchers: Vec<WrappedSearcher>,
+    text: &str,
+    module: Option<&str>,
+    json: bool,
+) -> Result<(), String> {
+    if let Some(module) = module {
+        searchers.retain(|s| s.key() == module || s.name().eq_ignore_ascii_case(module));
+        if searchers.is_empty() {
+            return Err(format!("No active module {module:?}"));
+        }
+    }
+    let session = Session::new(searchers, text, false);
+    let results: Vec<_> = session
+        .rows()
+        .iter()
+        .map(|(j, i)| {
+            let searcher = &session.searchers()[*j];
+            let handle = searcher.cached_matches()[*i];
+            let MatchInfo { name, desc, icon } = searcher.module().match_info(handle);
+            let mode = searcher.match_mode().unwrap_or_default();
+            QueryResult {
+                module: searcher.key(),
+                id: format!("{}:{}", searcher.key(), handle.0),
+                name,
+                desc,
+                icon,
+                score: best_match(name, desc, text, mode).map(|(_, score, _)| score),
+            }
+        })
+        .collect();
+    if json {
+        let text = serde_json::to_string_pretty(&results).map_err(|why| why.to_string())?;
+        println!("{text}");
+    } else {
+        for r in results {
+            let score = r.score.map(|s| s.to_string()).unwrap_or_default();
+            let fields = [r.module, &r.id, r.name, r.desc, r.icon, &score];
+            // NOTE: tabs and newlines would break up the columns
+            let fields: Vec<_> = fields
+                .iter()
+                .map(|f| f.replace(['\t', '\n'], " "))
+                .collect();
+            println!("{}", fields.join("\t"));
+        }
+    }
+    Ok(())
+}
+
+/// splits an id like "applications:42" into the module key and item handle
+fn parse_id(id: &str) -> Option<(&str, SearchItemHandle)> {
+    let (key, handle) = id.rsplit_once(':')?;
+    let handle: i32 = handle.parse().ok()?;
+    (handle >= 0).then_some((key, SearchItemHandle(handle)))
+}
+
+#[test]
+fn parse_id_test() {
+    assert_eq!(
+        parse_id("applications:42"),
+        Some(("applications", SearchItemHandle(42)))
+    );
+    assert_eq!(parse_id("a:b:3"), Some(("a:b", SearchItemHandle(3))));
+    assert_eq!(parse_id("symbols:-1"), None);
+    assert_eq!(parse_id("symbols"), None);
+}
+
+fn select(searchers: &[WrappedSearcher], id: &str) -> Result<(), String> {
+    let (key, handle) = parse_id(id).ok_or(format!("Invalid id {id:?}, expected module:n"))?;
+    let searcher = searchers
+        .iter()
+        .find(|s| s.key() == key)
+        .ok_or(format!("No active module {key:?}"))?;
+    let module = searcher.module();
+    // NOTE: otherwise the handle is trusted to be one printed by query, as modules that do not
+    // count their items cannot validate them
+    if module
+        .item_count()
+        .is_some_and(|count| handle.0 as usize >= count)
+    {
+        return Err(format!("No such item {id:?}"));
+    }
+    match module.completion(handle) {
+        Some(text) => println!("{text}"),
+        None => module.handle_selection(handle),
+    }
+    Ok(())
+}
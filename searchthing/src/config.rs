@@ -0,0 +1,59 @@
+//! The config file, `$XDG_CONFIG_HOME/searchthing/config.toml`, e.g.
+//!
+//! ```toml
+//! [keybindings]
+//! "Ctrl+L" = "clear-input"
+//! "Ctrl+K" = "none"
+//!
+//! [match-modes]
+//! applications = "initials"
+//! ```
+//!
+//! and window options, see [crate::geometry].
+
+use crate::{geometry::WindowConfig, xdg::xdg_dir};
+use egui_inspect::logging::log::warn;
+use serde::Deserialize;
+use std::{collections::HashMap, fs, io::ErrorKind, path::PathBuf, sync::OnceLock};
+
+#[derive(Deserialize, Default)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct Config {
+    /// key chords to command names, overriding the default bindings
+    pub keybindings: HashMap<String, String>,
+    /// module keys (e.g. "dmenu" or "applications") to the match mode they start in
+    pub match_modes: HashMap<String, String>,
+    pub window: WindowConfig,
+}
+
+fn config_path() -> PathBuf {
+    xdg_dir("XDG_CONFIG_HOME", ".config")
+        .join("searchthing")
+        .join("config.toml")
+}
+
+/// loaded once, as it is needed for both the window options and the app
+pub fn config() -> &'static Config {
+    static CONFIG: OnceLock<Config> = OnceLock::new();
+    CONFIG.get_or_init(Config::load)
+}
+
+impl Config {
+    /// the defaults are used without a config file, or when it fails to parse
+    fn load() -> Self {
+        let path = config_path();
+        let text = match fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(why) => {
+                if why.kind() != ErrorKind::NotFound {
+                    warn!("Failed to read config {path:?}: {why}");
+                }
+                return Default::default();
+            }
+        };
+        toml::from_str(&text).unwrap_or_else(|why| {
+            warn!("Failed to parse config {path:?}: {why}");
+            Default::default()
+        })
+    }
+}
@@ -0,0 +1,195 @@
+//! Translates dmenu, rofi (`-dmenu`), wofi and fuzzel (`--dmenu`) style arguments to searchthing
+//! ones, so that it can stand in for them in existing scripts. Used when the binary is linked as
+//! one of them, or given `--dmenu-compat` as the first argument, e.g.
+//!
+//! ```sh
+//! ln -s $(which searchthing) ~/.local/bin/dmenu
+//! printf 'a\nb\n' | searchthing --dmenu-compat -i -p "Pick one" -l 5
+//! ```
+//!
+//! Colour and theme options are ignored.
+
+use std::path::Path;
+
+/// names the binary may be linked as
+const COMPAT_NAMES: &[&str] = &["dmenu", "rofi", "wofi", "fuzzel"];
+
+/// options without an equivalent, that take a value
+const IGNORED_WITH_VALUE: &[&str] = &[
+    "-nb",
+    "-nf",
+    "-sb",
+    "-sf",
+    "-nhb",
+    "-nhf",
+    "-shb",
+    "-shf",
+    "-w",
+    "-m",
+    "-monitor",
+    "-theme",
+    "-theme-str",
+    "-location",
+    "-width",
+    "-a",
+    "-u",
+    "-config",
+    "--style",
+    "--conf",
+    "--width",
+    "--height",
+    "-W",
+    "-H",
+    "-s",
+    "-c",
+    "--color",
+    "--config",
+];
+
+/// options without an equivalent, or with the behaviour they ask for being the default
+const IGNORED: &[&str] = &[
+    "-dmenu",
+    "--dmenu",
+    "-d",
+    "-i",
+    "--insensitive",
+    "-b",
+    "-f",
+    "-v",
+    "-markup-rows",
+    "-no-fixed-num-lines",
+    "-show-icons",
+    "-sync",
+    "-normal-window",
+    "-auto-select",
+    "-no-sort",
+    "-case-sensitive",
+    "--no-sort",
+];
+
+/// The searchthing arguments for a dmenu style command line, or none if the binary is not being
+/// used as a stand-in.
+pub fn compat_args(argv: &[String]) -> Option<Vec<String>> {
+    let prog = argv.first()?;
+    let name = Path::new(prog).file_name()?.to_str()?;
+    let args = match argv.get(1) {
+        Some(flag) if flag == "--dmenu-compat" => &argv[2..],
+        _ if COMPAT_NAMES.contains(&name) => &argv[1..],
+        _ => return None,
+    };
+    Some(translate(prog, args))
+}
+
+fn translate(prog: &str, args: &[String]) -> Vec<String> {
+    let mut translated = vec![prog.to_string()];
+    let mut prompt = None;
+    // NOTE: long options may be given as --option=value
+    let mut args = args.iter().flat_map(|arg| match arg.split_once('=') {
+        Some((flag, value)) if arg.starts_with("--") => vec![flag.to_string(), value.to_string()],
+        _ => vec![arg.clone()],
+    });
+    while let Some(arg) = args.next() {
+        let equivalent = match arg.as_str() {
+            "-p" | "--prompt" => {
+                prompt = args.next();
+                continue;
+            }
+            "-l" | "-L" | "--lines" => "--queery-max",
+            "-fn" | "-font" | "--font" => "--main-font",
+            "-format" => "--format",
+            "-mesg" => "--message",
+            "-selected-row" => "--selected-row",
+            "-sep" => "--separator",
+            "-filter" => "--init-search",
+            "-matching" => {
+                let mode = match args.next().as_deref() {
+                    Some("normal" | "glob") => "substring",
+                    Some("prefix") => "prefix",
+                    Some("regex") => "regex",
+                    _ => "fuzzy",
+                };
+                translated.extend(["--match-mode".into(), mode.into()]);
+                continue;
+            }
+            "-no-custom" | "-only-match" => {
+                translated.push("--no-custom".into());
+                continue;
+            }
+            "-kb-accept-custom" => {
+                bind(&mut translated, args.next(), "accept-custom");
+                continue;
+            }
+            flag if flag.starts_with("-kb-custom-") => {
+                let command = &flag[1 + "kb-".len()..];
+                bind(&mut translated, args.next(), command);
+                continue;
+            }
+            "--index" => {
+                translated.extend(["--format".into(), "i".into()]);
+                continue;
+            }
+            "-multi-select" => {
+                translated.push("--multi-select".into());
+                continue;
+            }
+            flag if IGNORED_WITH_VALUE.contains(&flag) => {
+                args.next();
+                continue;
+            }
+            flag if IGNORED.contains(&flag) => continue,
+            _ => {
+                eprintln!("{prog}: ignoring unknown option {arg}");
+                continue;
+            }
+        };
+        translated.push(equivalent.into());
+        translated.extend(args.next());
+    }
+    // NOTE: last, as an optional value that could otherwise take the next argument
+    translated.push(match prompt {
+        Some(prompt) => format!("--dmenu={prompt}"),
+        None => "--dmenu".into(),
+    });
+    translated
+}
+
+/// binds each of rofi's comma separated chords to the command
+fn bind(translated: &mut Vec<String>, chords: Option<String>, command: &str) {
+    for chord in chords.iter().flat_map(|c| c.split(',')) {
+        translated.extend(["--bind".into(), format!("{chord}={command}")]);
+    }
+}
+
+#[test]
+fn compat_args_test() {
+    let argv = |args: &str| args.split(' ').map(String::from).collect::<Vec<_>>();
+    assert_eq!(compat_args(&argv("/usr/bin/searchthing -d x")), None);
+    assert_eq!(
+        compat_args(&argv("/bin/dmenu -i -l 5 -p Run -nb #000")),
+        Some(argv("/bin/dmenu --queery-max 5 --dmenu=Run"))
+    );
+    assert_eq!(
+        compat_args(&argv(
+            "searchthing --dmenu-compat -dmenu -format i -selected-row 2 -matching regex"
+        )),
+        Some(argv(
+            "searchthing --format i --selected-row 2 --match-mode regex --dmenu"
+        ))
+    );
+    assert_eq!(
+        compat_args(&argv("wofi --dmenu --prompt=Pick --lines=3 --bogus")),
+        Some(argv("wofi --queery-max 3 --dmenu=Pick"))
+    );
+    assert_eq!(
+        compat_args(&argv(
+            "rofi -dmenu -multi-select -no-custom -kb-custom-2 Alt+2,Alt+w"
+        )),
+        Some(argv(
+            "rofi --multi-select --no-custom --bind Alt+2=custom-2 --bind Alt+w=custom-2 --dmenu"
+        ))
+    );
+    assert_eq!(
+        compat_args(&argv("rofi -dmenu -sep | -filter fo")),
+        Some(argv("rofi --separator | --init-search fo --dmenu"))
+    );
+}
@@ -1,51 +1,227 @@
+use crate::{
+    icon_search::mtime,
+    xdg::{cache_dir, load_cache},
+};
 use egui_inspect::{
     egui::{
-        vec2, Color32, ColorImage, FontData, FontDefinitions, FontFamily, Image, RichText, Sense,
-        TextureHandle, Ui, Vec2,
+        self, vec2, Color32, ColorImage, FontData, FontDefinitions, FontFamily, Image, RichText,
+        Sense, TextureHandle, Ui, Vec2,
+    },
+    logging::log::{info, warn},
+};
+use fontdb::{Database, Family, Query, Stretch, Style, Weight, ID};
+use serde::{Deserialize, Serialize};
+use std::{
+    cell::{Cell, RefCell},
+    collections::BTreeMap,
+    fs,
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{channel, Receiver},
+        Arc, OnceLock,
     },
-    logging::log::info,
+    thread,
 };
-use std::{cell::RefCell, collections::BTreeMap, path::PathBuf, sync::Arc};
 use swash::{
-    scale::{ScaleContext, StrikeWith},
+    scale::{
+        image::{Content, Image as GlyphImage},
+        Render, ScaleContext, Source, StrikeWith,
+    },
+    zeno::Format,
     FontRef,
 };
-use walkdir::WalkDir;
 
-fn get_font_path(font_name: &str) -> Option<PathBuf> {
-    WalkDir::new("/usr/share/fonts/")
-        .into_iter()
-        .filter_map(|der| der.ok())
-        .find(|de| {
-            let fna = de.file_name().to_string_lossy();
-            fna.contains(format!("{font_name}.ttf").as_str())
-                || fna.contains(format!("{font_name}-Regular.ttf").as_str())
+/// System fonts, as listed by the fontconfig configuration (or the usual font dirs without it).
+fn font_db() -> &'static Database {
+    static FONTDB: OnceLock<Database> = OnceLock::new();
+    FONTDB.get_or_init(|| {
+        let mut db = Database::new();
+        db.load_system_fonts();
+        db
+    })
+}
+
+/// A fontconfig style font pattern, e.g. "Noto Sans:weight=light:slant=italic" or "Hack:bold".
+#[derive(Debug, PartialEq)]
+struct FontPattern {
+    family: String,
+    weight: Weight,
+    style: Style,
+    stretch: Stretch,
+}
+
+fn parse_weight(value: &str) -> Option<Weight> {
+    let weight = match value.to_lowercase().replace([' ', '-'], "").as_str() {
+        "thin" | "hairline" => Weight::THIN,
+        "extralight" | "ultralight" => Weight::EXTRA_LIGHT,
+        "light" => Weight::LIGHT,
+        "regular" | "normal" | "book" => Weight::NORMAL,
+        "medium" => Weight::MEDIUM,
+        "semibold" | "demibold" => Weight::SEMIBOLD,
+        "bold" => Weight::BOLD,
+        "extrabold" | "ultrabold" => Weight::EXTRA_BOLD,
+        "black" | "heavy" => Weight::BLACK,
+        // css style numeric weights
+        other => Weight(other.parse().ok()?),
+    };
+    Some(weight)
+}
+
+fn parse_style(value: &str) -> Option<Style> {
+    match value.to_lowercase().as_str() {
+        "roman" | "normal" | "upright" => Some(Style::Normal),
+        "italic" => Some(Style::Italic),
+        "oblique" => Some(Style::Oblique),
+        _ => None,
+    }
+}
+
+fn parse_stretch(value: &str) -> Option<Stretch> {
+    let stretch = match value.to_lowercase().replace([' ', '-'], "").as_str() {
+        "ultracondensed" => Stretch::UltraCondensed,
+        "extracondensed" => Stretch::ExtraCondensed,
+        "condensed" => Stretch::Condensed,
+        "semicondensed" => Stretch::SemiCondensed,
+        "normal" => Stretch::Normal,
+        "semiexpanded" => Stretch::SemiExpanded,
+        "expanded" => Stretch::Expanded,
+        "extraexpanded" => Stretch::ExtraExpanded,
+        "ultraexpanded" => Stretch::UltraExpanded,
+        _ => return None,
+    };
+    Some(stretch)
+}
+
+impl FontPattern {
+    fn parse(pattern: &str) -> Self {
+        let mut parts = pattern.split(':');
+        let family = parts.next().unwrap_or_default().trim();
+        // drop a point size suffix, e.g. "Noto Sans-12"
+        let family = match family.rsplit_once('-') {
+            Some((name, size)) if size.parse::<f32>().is_ok() => name,
+            _ => family,
+        };
+        let mut res = Self {
+            family: family.to_string(),
+            weight: Weight::NORMAL,
+            style: Style::Normal,
+            stretch: Stretch::Normal,
+        };
+        for prop in parts {
+            match prop.split_once('=') {
+                Some(("weight", v)) => res.weight = parse_weight(v).unwrap_or(res.weight),
+                Some(("slant", v)) => res.style = parse_style(v).unwrap_or(res.style),
+                Some(("width", v)) => res.stretch = parse_stretch(v).unwrap_or(res.stretch),
+                // a face style name such as "Light Italic"
+                Some(("style", v)) => {
+                    for word in v.split_whitespace() {
+                        res.weight = parse_weight(word).unwrap_or(res.weight);
+                        res.style = parse_style(word).unwrap_or(res.style);
+                        res.stretch = parse_stretch(word).unwrap_or(res.stretch);
+                    }
+                }
+                Some((k, _)) => warn!("Ignoring unsupported font property {k:?} in {pattern:?}"),
+                // constants such as ":bold" or ":italic"
+                None => {
+                    res.weight = parse_weight(prop).unwrap_or(res.weight);
+                    res.style = parse_style(prop).unwrap_or(res.style);
+                    res.stretch = parse_stretch(prop).unwrap_or(res.stretch);
+                }
+            }
+        }
+        res
+    }
+}
+
+#[test]
+fn parse_font_pattern_test() {
+    let p = FontPattern::parse("Noto Sans-12:weight=light:slant=italic");
+    assert_eq!(p.family, "Noto Sans");
+    assert_eq!(p.weight, Weight::LIGHT);
+    assert_eq!(p.style, Style::Italic);
+    let p = FontPattern::parse("Hack:style=Bold Italic");
+    assert_eq!((p.weight, p.style), (Weight::BOLD, Style::Italic));
+}
+
+/// Finds the face best matching a font pattern, family names are matched case insensitively, and
+/// postscript names or file names (e.g. "Ubuntu-Light") are also accepted.
+fn find_font(pattern: &str) -> Option<ID> {
+    let db = font_db();
+    let pat = FontPattern::parse(pattern);
+    let generic = match pat.family.to_lowercase().as_str() {
+        "sans-serif" | "sans" => Some(Family::SansSerif),
+        "serif" => Some(Family::Serif),
+        "monospace" | "mono" => Some(Family::Monospace),
+        _ => None,
+    };
+    let family_name = db.faces().find_map(|face| {
+        face.families
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(&pat.family))
+            .map(|(name, _)| name.as_str())
+    });
+    let family = generic.or(family_name.map(Family::Name));
+    let queried = family.and_then(|family| {
+        db.query(&Query {
+            families: &[family],
+            weight: pat.weight,
+            stretch: pat.stretch,
+            style: pat.style,
         })
-        .map(|de| de.path().to_owned())
+    });
+    queried.or_else(|| {
+        db.faces()
+            .find(|face| {
+                let file_stem = match &face.source {
+                    fontdb::Source::File(path) | fontdb::Source::SharedFile(path, _) => {
+                        path.file_stem().map(|s| s.to_string_lossy())
+                    }
+                    fontdb::Source::Binary(_) => None,
+                };
+                face.post_script_name.eq_ignore_ascii_case(&pat.family)
+                    || file_stem.is_some_and(|fs| fs.eq_ignore_ascii_case(&pat.family))
+            })
+            .map(|face| face.id)
+    })
 }
 
 thread_local! {
-    /// for use both directly in egui and via swash (for single multicolor glyph previews)
-    static STATICFONTS: RefCell<BTreeMap<String, &'static [u8]>> = Default::default();
+    /// The fonts in use, the font data being ref counted and shared between egui and swash (for
+    /// single multicolor glyph previews), so it is released once replaced in both.
+    static FONTS: RefCell<FontDefinitions> = RefCell::new(FontDefinitions::empty());
+    /// fonts being loaded in the background, dropped (so discarded) when others are set instead
+    static LOADING_FONTS: RefCell<Option<Receiver<FontDefinitions>>> = const { RefCell::new(None) };
+    /// whether [FONTS] changed since egui was last given them
+    static FONTS_CHANGED: Cell<bool> = const { Cell::new(false) };
+}
+
+fn load_font_data(font_name: &str) -> Option<Arc<FontData>> {
+    let db = font_db();
+    let Some(id) = find_font(font_name) else {
+        warn!("No installed font matches {font_name:?} (see `fc-list : family`), ignoring it");
+        return None;
+    };
+    if let Some(face) = db.face(id) {
+        info!(
+            "Loading {:?} (face {}) for {font_name:?}",
+            face.source, face.index
+        );
+    }
+    let (data, index) = db.with_face_data(id, |data, index| (data.to_vec(), index))?;
+    Some(Arc::new(FontData {
+        index,
+        ..FontData::from_owned(data)
+    }))
 }
 
-/// stores font in STATICFONTS and registers it with egui
 fn add_font_data(font_defs: &mut FontDefinitions, font_name: String) -> Option<String> {
-    let font_path = get_font_path(font_name.as_str())?;
-    info!("Loading {font_path:?}");
-    let font_data_ = std::fs::read(font_path).ok()?;
-    let sfd = font_data_.leak();
-    STATICFONTS.with_borrow_mut(|sf| {
-        sf.insert(font_name.clone(), sfd);
-    });
-    let font_data = FontData::from_static(sfd);
-    font_defs
-        .font_data
-        .insert(font_name.clone(), Arc::new(font_data));
+    let font_data = load_font_data(&font_name)?;
+    font_defs.font_data.insert(font_name.clone(), font_data);
     Some(font_name)
 }
 
-pub fn custom_egui_font_def(main: Option<String>, symbols: Vec<String>) -> FontDefinitions {
+fn custom_egui_font_def(main: Option<String>, symbols: Vec<String>) -> FontDefinitions {
     let mut font_defs = FontDefinitions::default();
     font_defs.families.clear();
     let my_font = match main.map(|mf| add_font_data(&mut font_defs, mf)) {
@@ -73,9 +249,304 @@ pub fn custom_egui_font_def(main: Option<String>, symbols: Vec<String>) -> FontD
     font_defs
 }
 
+/// (Re)loads the main and secondary fonts, replacing any previous ones along with discovered
+/// fallbacks and rendered symbols. Fonts given by name are found and read on a background thread,
+/// as listing the system fonts takes a while, the previous (or egui's own) fonts showing until
+/// then.
+pub fn set_fonts(ctx: &egui::Context, main: Option<String>, secondary: Vec<String>) {
+    LOADING_FONTS.take();
+    if main.is_none() && secondary.is_empty() {
+        replace_fonts(custom_egui_font_def(None, vec![]));
+        update_fonts(ctx);
+        return;
+    }
+    let (sender, receiver) = channel();
+    let ctx = ctx.clone();
+    thread::spawn(move || {
+        if sender.send(custom_egui_font_def(main, secondary)).is_ok() {
+            ctx.request_repaint();
+        }
+    });
+    LOADING_FONTS.replace(Some(receiver));
+}
+
+fn replace_fonts(font_defs: FontDefinitions) {
+    FONTS.replace(font_defs);
+    FONTS_CHANGED.set(true);
+    SYMBOLCACHE.with_borrow_mut(|sc| sc.cache.clear());
+}
+
+/// Gives egui the fonts loaded in the background and any fallbacks loaded since, called once a
+/// frame, as egui rebuilds its font atlas for each change.
+pub fn update_fonts(ctx: &egui::Context) {
+    let loaded = LOADING_FONTS.with_borrow(|loading| loading.as_ref()?.try_recv().ok());
+    if let Some(font_defs) = loaded {
+        LOADING_FONTS.take();
+        replace_fonts(font_defs);
+    }
+    if FONTS_CHANGED.replace(false) {
+        FONTS.with_borrow(|fonts| ctx.set_fonts(fonts.clone()));
+    }
+}
+
+/// A small panel for changing fonts at runtime.
+pub struct FontSettings {
+    main: String,
+    /// comma separated
+    secondary: String,
+}
+
+impl FontSettings {
+    pub fn new(main: &Option<String>, secondary: &[String]) -> Self {
+        Self {
+            main: main.clone().unwrap_or_default(),
+            secondary: secondary.join(", "),
+        }
+    }
+
+    pub fn show(&mut self, ui: &mut Ui) {
+        egui::Grid::new("font settings").show(ui, |ui| {
+            ui.label("Main font");
+            ui.text_edit_singleline(&mut self.main);
+            ui.end_row();
+            ui.label("Secondary fonts");
+            ui.text_edit_singleline(&mut self.secondary);
+            ui.end_row();
+        });
+        if ui.button("Apply").clicked() {
+            let main = Some(self.main.trim().to_string()).filter(|m| !m.is_empty());
+            let secondary = self
+                .secondary
+                .split(',')
+                .map(str::trim)
+                .filter(|s| !s.is_empty())
+                .map(String::from)
+                .collect();
+            set_fonts(ui.ctx(), main, secondary);
+        }
+    }
+}
+
+/// bump when the coverage index format changes
+const COVERAGE_VERSION: u32 = 1;
+
+/// The codepoints covered by a system font face.
+#[derive(Serialize, Deserialize, PartialEq)]
+struct FaceCoverage {
+    path: PathBuf,
+    index: u32,
+    mtime: u64,
+    name: String,
+    /// not bold, italic or otherwise styled, so preferred as a fallback
+    regular: bool,
+    /// sorted, inclusive codepoint ranges
+    ranges: Vec<(u32, u32)>,
+}
+
+impl FaceCoverage {
+    fn covers(&self, c: char) -> bool {
+        let c = c as u32;
+        let i = self.ranges.partition_point(|(_, end)| *end < c);
+        self.ranges.get(i).is_some_and(|(start, _)| *start <= c)
+    }
+}
+
+/// Which system fonts cover which codepoints, built by scanning their cmap tables once and
+/// cached on disk, for finding fallbacks for symbols not in the loaded fonts.
+#[derive(Serialize, Deserialize, Default)]
+struct CoverageIndex {
+    version: u32,
+    faces: Vec<FaceCoverage>,
+}
+
+fn codepoint_ranges(font: FontRef) -> Vec<(u32, u32)> {
+    let mut codepoints = vec![];
+    font.charmap().enumerate(|cp, glyph| {
+        if glyph != 0 {
+            codepoints.push(cp);
+        }
+    });
+    codepoints.sort_unstable();
+    codepoints.dedup();
+    let mut ranges: Vec<(u32, u32)> = vec![];
+    for cp in codepoints {
+        match ranges.last_mut() {
+            Some((_, end)) if *end + 1 == cp => *end = cp,
+            _ => ranges.push((cp, cp)),
+        }
+    }
+    ranges
+}
+
+impl CoverageIndex {
+    /// the font files to index, along with their face index and mtime, to check for staleness
+    fn sources() -> Vec<(PathBuf, u32, u64, ID)> {
+        font_db()
+            .faces()
+            .filter_map(|face| match &face.source {
+                fontdb::Source::File(path) | fontdb::Source::SharedFile(path, _) => {
+                    Some((path.clone(), face.index, mtime(path), face.id))
+                }
+                fontdb::Source::Binary(_) => None,
+            })
+            .collect()
+    }
+
+    fn build(sources: &[(PathBuf, u32, u64, ID)]) -> Self {
+        let db = font_db();
+        let faces = sources
+            .iter()
+            .filter_map(|(path, index, mtime, id)| {
+                let face = db.face(*id)?;
+                let ranges = db.with_face_data(*id, |data, index| {
+                    FontRef::from_index(data, index as usize).map(codepoint_ranges)
+                })??;
+                Some(FaceCoverage {
+                    path: path.clone(),
+                    index: *index,
+                    mtime: *mtime,
+                    name: face.post_script_name.clone(),
+                    regular: face.weight == Weight::NORMAL
+                        && face.style == Style::Normal
+                        && face.stretch == Stretch::Normal,
+                    ranges,
+                })
+            })
+            .collect();
+        Self {
+            version: COVERAGE_VERSION,
+            faces,
+        }
+    }
+
+    /// uses the cached index if no font file has changed, rebuilding it otherwise
+    fn load() -> Self {
+        let sources = Self::sources();
+        let is_current = |cached: &Self| {
+            let unchanged = cached.faces.len() == sources.len()
+                && cached
+                    .faces
+                    .iter()
+                    .zip(&sources)
+                    .all(|(fc, (path, index, mtime, _))| {
+                        (&fc.path, fc.index, fc.mtime) == (path, *index, *mtime)
+                    });
+            cached.version == COVERAGE_VERSION && unchanged
+        };
+        load_cache(
+            &cache_dir().join("font_coverage.json"),
+            "font coverage index",
+            is_current,
+            || Self::build(&sources),
+        )
+    }
+
+    /// a face covering the symbol, preferring regular styles
+    fn find(&self, symbol: char) -> Option<&FaceCoverage> {
+        self.faces
+            .iter()
+            .filter(|fc| fc.covers(symbol))
+            .min_by_key(|fc| !fc.regular)
+    }
+}
+
+static COVERAGE: OnceLock<CoverageIndex> = OnceLock::new();
+static COVERAGE_STARTED: AtomicBool = AtomicBool::new(false);
+
+/// the coverage index if ready, starting to load it in the background otherwise
+fn coverage_index(ctx: &egui::Context) -> Option<&'static CoverageIndex> {
+    if let Some(ci) = COVERAGE.get() {
+        return Some(ci);
+    }
+    if !COVERAGE_STARTED.swap(true, Ordering::SeqCst) {
+        let ctx = ctx.clone();
+        thread::spawn(move || {
+            let _ = COVERAGE.set(CoverageIndex::load());
+            ctx.request_repaint();
+        });
+    }
+    None
+}
+
+fn font_covers(font_data: &FontData, symbol: char) -> bool {
+    FontRef::from_index(&font_data.font, font_data.index as usize)
+        .is_some_and(|f| f.charmap().map(symbol) != 0)
+}
+
+/// loads a fallback font, for both swash and egui (at the lowest priority, and given to egui on the
+/// next frame along with any others loaded in this one)
+fn load_fallback(ctx: &egui::Context, face: &FaceCoverage) {
+    let name = format!("fallback:{}", face.name);
+    if FONTS.with_borrow(|fonts| fonts.font_data.contains_key(&name)) {
+        return;
+    }
+    let data = match fs::read(&face.path) {
+        Ok(data) => data,
+        Err(why) => {
+            warn!("Failed to read fallback font {:?}: {why}", face.path);
+            return;
+        }
+    };
+    info!(
+        "Loading fallback font {:?} (face {})",
+        face.path, face.index
+    );
+    let font_data = FontData {
+        index: face.index,
+        ..FontData::from_owned(data)
+    };
+    FONTS.with_borrow_mut(|fonts| {
+        fonts.font_data.insert(name.clone(), Arc::new(font_data));
+        // NOTE: symbols are drawn in the proportional family, so only it needs fallbacks
+        let family = fonts.families.entry(FontFamily::Proportional).or_default();
+        family.push(name);
+    });
+    FONTS_CHANGED.set(true);
+    ctx.request_repaint();
+}
+
+/// Glyph sources preferred for symbols, layered COLR outlines (v0 only in swash) and colour bitmap
+/// strikes, scaled to the nearest strike.
+const COLOR_SOURCES: &[Source] = &[
+    Source::ColorOutline(0),
+    Source::ColorBitmap(StrikeWith::BestFit),
+];
+/// Plain outlines, rasterised as a white mask (matching the text fallback).
+const OUTLINE_SOURCES: &[Source] = &[Source::Outline];
+
+/// converts a rendered glyph into an image, centred on a square canvas of at least `px`
+fn glyph_to_image(glyph: &GlyphImage, px: u32) -> Option<ColorImage> {
+    let (w, h) = (
+        glyph.placement.width as usize,
+        glyph.placement.height as usize,
+    );
+    if w == 0 || h == 0 {
+        return None;
+    }
+    let rgba: Vec<u8> = match glyph.content {
+        Content::Mask => glyph
+            .data
+            .iter()
+            .flat_map(|&a| [255, 255, 255, a])
+            .collect(),
+        Content::Color => glyph.data.clone(),
+        Content::SubpixelMask => return None,
+    };
+    let side = w.max(h).max(px as usize);
+    let (x0, y0) = ((side - w) / 2, (side - h) / 2);
+    let mut canvas = vec![0; side * side * 4];
+    for row in 0..h {
+        let dst = ((y0 + row) * side + x0) * 4;
+        canvas[dst..dst + w * 4].copy_from_slice(&rgba[row * w * 4..(row + 1) * w * 4]);
+    }
+    Some(ColorImage::from_rgba_unmultiplied([side, side], &canvas))
+}
+
 #[derive(Default)]
 pub struct SymbolImageCache {
-    cache: BTreeMap<char, Option<TextureHandle>>,
+    /// textures per symbol and size in physical pixels
+    cache: BTreeMap<(char, u32), Option<TextureHandle>>,
+    scale_context: ScaleContext,
 }
 
 thread_local! {
@@ -83,54 +554,92 @@ thread_local! {
 }
 
 impl SymbolImageCache {
-    fn try_load_image(&mut self, ui: &mut Ui, font: FontRef, symbol: char) -> bool {
+    /// renders the symbol at the exact pixel size from the first of `sources` the font provides
+    fn render(
+        &mut self,
+        font: FontRef,
+        symbol: char,
+        px: u32,
+        sources: &[Source],
+    ) -> Option<ColorImage> {
         let glyph_id = font.charmap().map(symbol);
-
-        let mut scale_context = ScaleContext::default();
-        let mut scaler = scale_context.builder(font).build();
-        let handle = scaler
-            .scale_color_bitmap(glyph_id, StrikeWith::LargestSize)
-            .map(|image| {
-                let cimage = ColorImage::from_rgba_unmultiplied(
-                    [
-                        image.placement.width as usize,
-                        image.placement.height as usize,
-                    ],
-                    image.data.as_slice(),
-                );
-                ui.ctx().load_texture(symbol, cimage, Default::default())
-            });
-        self.cache.insert(symbol, handle.clone());
-        handle.is_some()
+        if glyph_id == 0 {
+            return None;
+        }
+        let mut scaler = self
+            .scale_context
+            .builder(font)
+            .size(px as f32)
+            .hint(false)
+            .build();
+        let glyph = Render::new(sources)
+            .format(Format::Alpha)
+            .render(&mut scaler, glyph_id)?;
+        glyph_to_image(&glyph, px)
+    }
+    /// tries colour glyphs in all loaded fonts before falling back to the first outline found
+    fn load_image(&mut self, ui: &mut Ui, symbol: char, px: u32) {
+        let image = FONTS.with_borrow(|fonts| {
+            let fonts: Vec<_> = fonts
+                .families
+                .values()
+                .flatten()
+                .filter_map(|name| fonts.font_data.get(name))
+                .collect();
+            [COLOR_SOURCES, OUTLINE_SOURCES].iter().find_map(|sources| {
+                fonts.iter().find_map(|fd| {
+                    let font = FontRef::from_index(&fd.font, fd.index as usize)?;
+                    self.render(font, symbol, px, sources)
+                })
+            })
+        });
+        let handle = image.map(|image| {
+            ui.ctx()
+                .load_texture(format!("{symbol}@{px}"), image, Default::default())
+        });
+        self.cache.insert((symbol, px), handle);
     }
     pub fn inspect(&mut self, ui: &mut Ui, symbol: char, size: Vec2) {
-        match self.cache.get(&symbol) {
+        let px = (size.y * ui.ctx().pixels_per_point()).round() as u32;
+        match self.cache.get(&(symbol, px)) {
             Some(Some(th)) => {
                 ui.add(Image::new(th).fit_to_exact_size(size));
             }
             None => {
-                // NOTE: allowing a 1 frame delay here, also all newly visible glyphs will load at
-                // once here
-                STATICFONTS.with_borrow(|sf| {
-                    for data in sf.values() {
-                        let font = swash::FontRef::from_index(data, 0).unwrap();
-                        if self.try_load_image(ui, font, symbol) {
-                            break;
+                let loaded = FONTS.with_borrow(|fonts| {
+                    fonts.font_data.values().any(|fd| font_covers(fd, symbol))
+                });
+                if !loaded {
+                    match coverage_index(ui.ctx()) {
+                        Some(ci) => {
+                            if let Some(face) = ci.find(symbol) {
+                                load_fallback(ui.ctx(), face);
+                            }
+                        }
+                        None => {
+                            // retried once the coverage index is ready
+                            Self::symbol_label(ui, symbol, size);
+                            return;
                         }
                     }
-                });
-            }
-            _ => {
-                let rt = RichText::from(format!("{symbol}"))
-                    .size(size.y)
-                    .color(Color32::WHITE);
-                let r = ui.label(rt).rect;
-                let s = ui.spacing().item_spacing.x;
-                let d = size.x - r.width() - s;
-                if d > 0.0 {
-                    ui.allocate_exact_size(vec2(d, 0.0), Sense::empty());
                 }
+                // NOTE: allowing a 1 frame delay here, also all newly visible glyphs will load at
+                // once here
+                self.load_image(ui, symbol, px);
             }
+            _ => Self::symbol_label(ui, symbol, size),
+        }
+    }
+    /// draws the symbol as text, through the egui font families
+    fn symbol_label(ui: &mut Ui, symbol: char, size: Vec2) {
+        let rt = RichText::from(format!("{symbol}"))
+            .size(size.y)
+            .color(Color32::WHITE);
+        let r = ui.label(rt).rect;
+        let s = ui.spacing().item_spacing.x;
+        let d = size.x - r.width() - s;
+        if d > 0.0 {
+            ui.allocate_exact_size(vec2(d, 0.0), Sense::empty());
         }
     }
 }
@@ -0,0 +1,192 @@
+//! Window size, position and match layout, set in the `[window]` table of the config file, e.g.
+//!
+//! ```toml
+//! [window]
+//! width = "40%"
+//! height = 400
+//! anchor = "top"
+//! auto-height = true
+//! layout = "compact"
+//! ```
+
+use egui_inspect::{
+    egui::{pos2, vec2, Pos2, Vec2},
+    logging::log::warn,
+};
+use serde::Deserialize;
+
+/// A window dimension, in points or as a percentage of the monitor (e.g. "50%")
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+#[serde(untagged)]
+pub enum Length {
+    Points(f32),
+    Text(String),
+}
+
+impl Length {
+    pub fn resolve(&self, monitor: f32) -> Option<f32> {
+        match self {
+            Length::Points(p) => Some(*p),
+            Length::Text(text) => match text.trim().strip_suffix('%') {
+                Some(percent) => percent
+                    .trim()
+                    .parse::<f32>()
+                    .ok()
+                    .map(|p| p * monitor / 100.0),
+                None => text.trim().parse().ok(),
+            },
+        }
+    }
+    /// the size in points if given without reference to the monitor
+    fn absolute(&self) -> Option<f32> {
+        match self {
+            Length::Points(p) => Some(*p),
+            Length::Text(text) => text.trim().parse().ok(),
+        }
+    }
+}
+
+#[test]
+fn resolve_length_test() {
+    assert_eq!(Length::Points(800.0).resolve(1920.0), Some(800.0));
+    assert_eq!(Length::Text("50%".into()).resolve(1920.0), Some(960.0));
+    assert_eq!(Length::Text(" 300 ".into()).resolve(1920.0), Some(300.0));
+    assert_eq!(Length::Text("50%".into()).absolute(), None);
+    assert_eq!(Length::Text("wide".into()).resolve(1920.0), None);
+}
+
+/// Where the window is placed on the monitor
+#[derive(Deserialize, Clone, Copy, Default, Debug, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum Anchor {
+    #[default]
+    Center,
+    Top,
+    Bottom,
+    Left,
+    Right,
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+    /// centred on the mouse pointer (X11 only, centred on the monitor otherwise)
+    Cursor,
+}
+
+/// How each match is drawn
+#[derive(Deserialize, Clone, Copy, Default, Debug, PartialEq, clap::ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum Layout {
+    /// name and description on separate lines
+    #[default]
+    List,
+    /// name and description on a single line
+    Compact,
+    /// a grid of icons, with the name beneath (e.g. for symbols)
+    Grid,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct WindowConfig {
+    pub width: Length,
+    pub height: Length,
+    pub anchor: Anchor,
+    /// shrink the window to fit the matches, up to the given height
+    pub auto_height: bool,
+    pub layout: Layout,
+    /// distance from the anchored edges, with the layer shell backend
+    pub margin: i32,
+}
+
+impl Default for WindowConfig {
+    fn default() -> Self {
+        Self {
+            width: Length::Points(800.0),
+            height: Length::Points(400.0),
+            anchor: Default::default(),
+            auto_height: false,
+            layout: Default::default(),
+            margin: 0,
+        }
+    }
+}
+
+/// Used until the monitor size is known.
+const FALLBACK_SIZE: Vec2 = vec2(800.0, 400.0);
+
+impl WindowConfig {
+    /// the size set when creating the window, before the monitor size is known
+    pub fn initial_size(&self) -> Vec2 {
+        vec2(
+            self.width.absolute().unwrap_or(FALLBACK_SIZE.x),
+            self.height.absolute().unwrap_or(FALLBACK_SIZE.y),
+        )
+    }
+
+    /// the (maximum, with auto height) window size on the given monitor
+    pub fn size(&self, monitor: Vec2) -> Vec2 {
+        let resolve = |length: &Length, monitor: f32, fallback: f32| {
+            length.resolve(monitor).unwrap_or_else(|| {
+                warn!("Invalid window dimension {length:?}, using {fallback}");
+                fallback
+            })
+        };
+        vec2(
+            resolve(&self.width, monitor.x, FALLBACK_SIZE.x).min(monitor.x),
+            resolve(&self.height, monitor.y, FALLBACK_SIZE.y).min(monitor.y),
+        )
+    }
+
+    /// the top left of a window of the given size, the pointer position is used for
+    /// [Anchor::Cursor]
+    pub fn position(&self, monitor: Vec2, size: Vec2, pointer: Option<Pos2>) -> Pos2 {
+        let free = monitor - size;
+        let (x, y) = match self.anchor {
+            Anchor::Center => (0.5, 0.5),
+            Anchor::Top => (0.5, 0.0),
+            Anchor::Bottom => (0.5, 1.0),
+            Anchor::Left => (0.0, 0.5),
+            Anchor::Right => (1.0, 0.5),
+            Anchor::TopLeft => (0.0, 0.0),
+            Anchor::TopRight => (1.0, 0.0),
+            Anchor::BottomLeft => (0.0, 1.0),
+            Anchor::BottomRight => (1.0, 1.0),
+            Anchor::Cursor => match pointer {
+                Some(p) => {
+                    let p = p - size / 2.0;
+                    return pos2(p.x.clamp(0.0, free.x), p.y.clamp(0.0, free.y));
+                }
+                None => (0.5, 0.5),
+            },
+        };
+        pos2(free.x * x, free.y * y)
+    }
+}
+
+#[test]
+fn window_position_test() {
+    let window = WindowConfig {
+        anchor: Anchor::Top,
+        ..Default::default()
+    };
+    let monitor = vec2(1920.0, 1080.0);
+    let size = window.size(monitor);
+    assert_eq!(window.position(monitor, size, None), pos2(560.0, 0.0));
+    let window = WindowConfig {
+        anchor: Anchor::Cursor,
+        ..window
+    };
+    let pointer = Some(pos2(1900.0, 500.0));
+    assert_eq!(window.position(monitor, size, pointer), pos2(1120.0, 300.0));
+}
+
+/// The mouse pointer position in physical pixels, through X11 (as it is not exposed to clients
+/// on wayland).
+pub fn pointer_position() -> Option<Pos2> {
+    use x11rb::{connection::Connection, protocol::xproto::ConnectionExt};
+    let (conn, screen) = x11rb::connect(None).ok()?;
+    let root = conn.setup().roots.get(screen)?.root;
+    let reply = conn.query_pointer(root).ok()?.reply().ok()?;
+    Some(pos2(reply.root_x as f32, reply.root_y as f32))
+}
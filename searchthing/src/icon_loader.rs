@@ -0,0 +1,134 @@
+//! Resolves and decodes icons on background threads, so that filesystem access and image
+//! decoding stay off the egui thread.
+
+use crate::{icon_search::IconLookup, ui::Icon};
+use egui_inspect::{
+    egui::{self, load::SizeHint, ColorImage, TextureHandle, TextureOptions},
+    logging::log::warn,
+};
+use searchthing_interface::char_from_codepoint;
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    path::Path,
+    sync::{
+        mpsc::{channel, Receiver, Sender},
+        Arc, Mutex, OnceLock,
+    },
+    thread,
+};
+
+const RESOLVER_THREADS: usize = 2;
+
+/// decodes an icon file, rasterising or downscaling it to the given pixel size
+fn decode_icon(path: &Path, px: u32) -> Result<ColorImage, String> {
+    let bytes = fs::read(path).map_err(|e| e.to_string())?;
+    if path.extension().is_some_and(|ext| ext == "svg") {
+        return egui_extras::image::load_svg_bytes_with_size(&bytes, Some(SizeHint::Size(px, px)));
+    }
+    let mut image = image::load_from_memory(&bytes).map_err(|e| e.to_string())?;
+    if image.width() > px || image.height() > px {
+        image = image.resize(px, px, image::imageops::FilterType::Triangle);
+    }
+    let rgba = image.to_rgba8();
+    Ok(ColorImage::from_rgba_unmultiplied(
+        [rgba.width() as usize, rgba.height() as usize],
+        rgba.as_raw(),
+    ))
+}
+
+fn resolver_loop(
+    ctx: egui::Context,
+    lookup: Arc<OnceLock<IconLookup>>,
+    theme: Option<String>,
+    icon_size: f32,
+    requests: Arc<Mutex<Receiver<String>>>,
+    ready: Sender<(String, Option<TextureHandle>)>,
+) {
+    loop {
+        // NOTE: lock only held while waiting, so the other workers queue up behind it
+        let name = match requests.lock().unwrap().recv() {
+            Ok(name) => name,
+            Err(_) => return,
+        };
+        let lookup = lookup.get_or_init(|| IconLookup::new(theme.clone()));
+        let ppp = ctx.pixels_per_point();
+        let scale = (ppp.round() as u32).max(1);
+        let texture = lookup
+            .find_icon(&name, icon_size.round() as u32, scale)
+            .and_then(
+                |path| match decode_icon(&path, (icon_size * ppp).round() as u32) {
+                    Ok(image) => Some(ctx.load_texture(&name, image, TextureOptions::LINEAR)),
+                    Err(why) => {
+                        warn!("Failed to load icon {path:?}: {why}");
+                        None
+                    }
+                },
+            );
+        if ready.send((name, texture)).is_err() {
+            return;
+        }
+        ctx.request_repaint();
+    }
+}
+
+pub struct AppIconCache {
+    /// name to texture map, None when not found or not loadable
+    store: HashMap<String, Option<TextureHandle>>,
+    /// names sent to the resolver threads and not yet received back
+    pending: HashSet<String>,
+    requests: Sender<String>,
+    ready: Receiver<(String, Option<TextureHandle>)>,
+}
+
+impl AppIconCache {
+    /// uses the given icon theme, or the one configured for the desktop otherwise
+    pub fn new(ctx: &egui::Context, theme: Option<String>, icon_size: f32) -> Self {
+        let (requests, request_rx) = channel();
+        let (ready_tx, ready) = channel();
+        let request_rx = Arc::new(Mutex::new(request_rx));
+        let lookup = Arc::new(OnceLock::new());
+        for _ in 0..RESOLVER_THREADS {
+            let (ctx, lookup, theme) = (ctx.clone(), lookup.clone(), theme.clone());
+            let (request_rx, ready_tx) = (request_rx.clone(), ready_tx.clone());
+            thread::spawn(move || {
+                resolver_loop(ctx, lookup, theme, icon_size, request_rx, ready_tx)
+            });
+        }
+        Self {
+            store: Default::default(),
+            pending: Default::default(),
+            requests,
+            ready,
+        }
+    }
+
+    /// collect icons which have finished loading since the last frame
+    pub fn receive(&mut self) {
+        for (name, texture) in self.ready.try_iter() {
+            self.pending.remove(&name);
+            self.store.insert(name, texture);
+        }
+    }
+
+    pub fn get(&mut self, name: &str) -> Icon {
+        if name.is_empty() {
+            return Icon::None;
+        }
+        if name.starts_with("U+") {
+            return char_from_codepoint(name)
+                .map(|c| Icon::Unicode { c })
+                .unwrap_or(Icon::NotFound);
+        }
+        match self.store.get(name) {
+            Some(Some(texture)) => Icon::Found { texture },
+            Some(None) => Icon::NotFound,
+            None => {
+                if !self.pending.contains(name) && self.requests.send(name.to_string()).is_ok() {
+                    self.pending.insert(name.to_string());
+                }
+                Icon::Loading
+            }
+        }
+    }
+}
@@ -1,53 +1,458 @@
-use std::{collections::HashMap, env, path::PathBuf};
-
-use walkdir::{DirEntry, WalkDir};
-
-fn icons_iterator() -> impl Iterator<Item = DirEntry> {
-    // TODO: gracefully handle missing paths?
-    // TODO: priortise higher detail subfolders?
-    let home_path = PathBuf::from(env::var("HOME").unwrap());
-    let local_icons = home_path.join(".local/share/icons");
-    WalkDir::new("/usr/share/icons")
-        .into_iter()
-        .chain(WalkDir::new(local_icons))
-        .chain(WalkDir::new("/var/lib/flatpak/exports/share/icons"))
-        .chain(WalkDir::new("/usr/share/pixmaps"))
-        .filter_map(|der| der.ok())
+//! Icon lookup following the freedesktop Icon Theme Specification, through an index of the theme
+//! directories which is cached on disk.
+
+use crate::xdg::{cache_dir, home_dir, load_cache, xdg_dir};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{HashMap, HashSet},
+    env, fs,
+    path::{Path, PathBuf},
+    time::UNIX_EPOCH,
+};
+
+// NOTE: xpm is also allowed by the spec, but is not supported by the image loaders
+const EXTENSIONS: &[&str] = &["png", "svg"];
+
+/// bump when the index format changes
+const INDEX_VERSION: u32 = 1;
+
+/// Directories searched for themes, in order of precedence, as listed in the spec (plus flatpak
+/// exports, for when they have not been added to XDG_DATA_DIRS).
+fn base_dirs() -> Vec<PathBuf> {
+    let data_dirs =
+        env::var("XDG_DATA_DIRS").unwrap_or_else(|_| "/usr/local/share:/usr/share".into());
+    let mut dirs = vec![
+        home_dir().join(".icons"),
+        xdg_dir("XDG_DATA_HOME", ".local/share").join("icons"),
+    ];
+    dirs.extend(data_dirs.split(':').map(|d| Path::new(d).join("icons")));
+    dirs.push(xdg_dir("XDG_DATA_HOME", ".local/share").join("flatpak/exports/share/icons"));
+    dirs.push("/var/lib/flatpak/exports/share/icons".into());
+    dirs.push("/usr/share/pixmaps".into());
+    let mut seen = HashSet::new();
+    dirs.retain(|d| d.is_dir() && seen.insert(d.clone()));
+    dirs
+}
+
+/// Reads a key from the first section with that key in an ini style file.
+fn ini_value(content: &str, key: &str) -> Option<String> {
+    content.lines().find_map(|line| {
+        let (k, v) = line.split_once('=')?;
+        (k.trim() == key).then(|| v.trim().trim_matches('"').to_string())
+    })
+}
+
+/// The icon theme set for gtk or kde, falling back on hicolor.
+pub fn current_theme_name() -> String {
+    let config = xdg_dir("XDG_CONFIG_HOME", ".config");
+    let candidates = [
+        (config.join("gtk-4.0/settings.ini"), "gtk-icon-theme-name"),
+        (config.join("gtk-3.0/settings.ini"), "gtk-icon-theme-name"),
+        (home_dir().join(".gtkrc-2.0"), "gtk-icon-theme-name"),
+        (config.join("kdeglobals"), "Theme"),
+    ];
+    candidates
+        .iter()
+        .find_map(|(path, key)| ini_value(&fs::read_to_string(path).ok()?, key))
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| "hicolor".into())
 }
 
-#[allow(dead_code)]
-pub fn find_icon(name: &str) -> Option<DirEntry> {
-    icons_iterator().find(|de| de.file_name().to_string_lossy().contains(name))
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum DirType {
+    Fixed,
+    Scalable,
+    Threshold,
+}
+
+#[derive(Debug)]
+struct ThemeDir {
+    subdir: String,
+    size: u32,
+    scale: u32,
+    kind: DirType,
+    min_size: u32,
+    max_size: u32,
+    threshold: u32,
+}
+
+impl ThemeDir {
+    fn matches_size(&self, size: u32, scale: u32) -> bool {
+        if self.scale != scale {
+            return false;
+        }
+        match self.kind {
+            DirType::Fixed => self.size == size,
+            DirType::Scalable => self.min_size <= size && size <= self.max_size,
+            DirType::Threshold => {
+                self.size.saturating_sub(self.threshold) <= size
+                    && size <= self.size + self.threshold
+            }
+        }
+    }
+
+    fn size_distance(&self, size: u32, scale: u32) -> u32 {
+        let target = size * scale;
+        let (min, max) = match self.kind {
+            DirType::Fixed => (self.size, self.size),
+            DirType::Scalable => (self.min_size, self.max_size),
+            DirType::Threshold => (
+                self.size.saturating_sub(self.threshold),
+                self.size + self.threshold,
+            ),
+        };
+        if target < min * self.scale {
+            min * self.scale - target
+        } else {
+            target.saturating_sub(max * self.scale)
+        }
+    }
+}
+
+#[derive(Debug)]
+struct IconTheme {
+    inherits: Vec<String>,
+    dirs: Vec<ThemeDir>,
+}
+
+impl IconTheme {
+    fn parse(index: &str) -> Self {
+        let mut inherits = vec![];
+        let mut dir_names = vec![];
+        let mut sections: HashMap<&str, HashMap<&str, &str>> = HashMap::new();
+        let mut section = "";
+        for line in index.lines().map(str::trim) {
+            if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                section = name;
+            } else if let Some((k, v)) = line.split_once('=') {
+                sections
+                    .entry(section)
+                    .or_default()
+                    .insert(k.trim(), v.trim());
+            }
+        }
+        if let Some(header) = sections.get("Icon Theme") {
+            let list = |key| {
+                header
+                    .get(key)
+                    .map(|v: &&str| {
+                        v.split(',')
+                            .map(str::trim)
+                            .filter(|s| !s.is_empty())
+                            .map(String::from)
+                            .collect::<Vec<_>>()
+                    })
+                    .unwrap_or_default()
+            };
+            inherits = list("Inherits");
+            dir_names = list("Directories");
+            dir_names.extend(list("ScaledDirectories"));
+        }
+        let dirs = dir_names
+            .into_iter()
+            .filter_map(|subdir| {
+                let keys = sections.get(subdir.as_str())?;
+                let num = |key, default| {
+                    keys.get(key)
+                        .and_then(|v: &&str| v.parse().ok())
+                        .unwrap_or(default)
+                };
+                let size = num("Size", 0);
+                let kind = match keys.get("Type") {
+                    Some(&"Fixed") => DirType::Fixed,
+                    Some(&"Scalable") => DirType::Scalable,
+                    _ => DirType::Threshold,
+                };
+                Some(ThemeDir {
+                    size,
+                    scale: num("Scale", 1),
+                    kind,
+                    min_size: num("MinSize", size),
+                    max_size: num("MaxSize", size),
+                    threshold: num("Threshold", 2),
+                    subdir,
+                })
+            })
+            .collect();
+        Self { inherits, dirs }
+    }
 }
 
 #[test]
-fn find_icon_test() {
-    dbg!(find_icon("firefox"));
-    dbg!(find_icon("neovim"));
-}
-
-pub fn find_files(
-    files_iter: impl Iterator<Item = DirEntry>,
-    mut names: Vec<String>,
-) -> HashMap<String, DirEntry> {
-    let mut res = HashMap::new();
-    if !names.is_empty() {
-        for de in files_iter {
-            let file_name = de.file_name().to_string_lossy();
-            let _match = names
-                .iter()
-                .enumerate()
-                .find(|(_, name)| file_name.contains(name.as_str()));
-            if let Some((idx, name)) = _match {
-                res.insert(name.clone(), de);
-                names.remove(idx);
+fn parse_index_theme_test() {
+    let theme = IconTheme::parse(
+        "[Icon Theme]\nName=Test\nInherits=Adwaita,hicolor\nDirectories=48x48/apps,scalable/apps\n\n\
+         [48x48/apps]\nSize=48\nType=Fixed\n\n[scalable/apps]\nSize=128\nMinSize=8\nMaxSize=512\nType=Scalable\n",
+    );
+    assert_eq!(theme.inherits, vec!["Adwaita", "hicolor"]);
+    assert_eq!(theme.dirs.len(), 2);
+    assert!(theme.dirs[0].matches_size(48, 1));
+    assert!(!theme.dirs[0].matches_size(32, 1));
+    assert_eq!(theme.dirs[0].size_distance(32, 1), 16);
+    assert!(theme.dirs[1].matches_size(32, 1));
+}
+
+pub fn mtime(path: &Path) -> u64 {
+    fs::metadata(path)
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// an icon file found in a theme directory
+#[derive(Serialize, Deserialize)]
+struct Candidate {
+    /// index into [IconLookup::themes]
+    theme: usize,
+    /// index into the theme's [IconTheme::dirs]
+    dir: usize,
+    path: PathBuf,
+}
+
+/// Every icon file in the theme chain, by icon name.
+#[derive(Serialize, Deserialize, Default)]
+struct IconIndex {
+    version: u32,
+    /// all indexed directories (present or not), used to detect when the index is stale
+    dir_mtimes: Vec<(PathBuf, u64)>,
+    /// candidates in lookup order (theme, then theme dir, then base dir)
+    icons: HashMap<String, Vec<Candidate>>,
+    /// unthemed icons placed directly in the base dirs (or pixmaps)
+    fallback: HashMap<String, PathBuf>,
+}
+
+/// icon name and extension of a file, if it is of a supported type
+fn icon_file_name(path: &Path) -> Option<String> {
+    let ext = path.extension()?.to_str()?;
+    EXTENSIONS.contains(&ext).then_some(())?;
+    Some(path.file_stem()?.to_string_lossy().into())
+}
+
+fn list_icons(dir: &Path) -> Vec<(String, PathBuf)> {
+    let Ok(rd) = fs::read_dir(dir) else {
+        return vec![];
+    };
+    let mut icons: Vec<_> = rd
+        .filter_map(|der| der.ok())
+        .map(|de| de.path())
+        .filter_map(|path| Some((icon_file_name(&path)?, path)))
+        .collect();
+    // prefer extensions in the order listed
+    icons.sort_by_key(|(_, path)| {
+        let ext = path.extension().unwrap_or_default();
+        EXTENSIONS.iter().position(|e| ext == *e)
+    });
+    icons
+}
+
+/// Resolves icon names to files, through the current theme, the themes it inherits and hicolor.
+pub struct IconLookup {
+    base_dirs: Vec<PathBuf>,
+    /// the theme and its ancestors, in lookup order, ending with hicolor
+    themes: Vec<(String, IconTheme)>,
+    index: IconIndex,
+    /// where the index is cached
+    cache_path: PathBuf,
+}
+
+impl IconLookup {
+    pub fn new(theme: Option<String>) -> Self {
+        Self::with_dirs(theme, base_dirs(), cache_dir().join("icon_index.json"))
+    }
+
+    /// looks in the given base dirs rather than those of the spec, caching the index at the path
+    fn with_dirs(theme: Option<String>, base_dirs: Vec<PathBuf>, cache_path: PathBuf) -> Self {
+        let mut lookup = Self {
+            base_dirs,
+            themes: vec![],
+            index: Default::default(),
+            cache_path,
+        };
+        let mut visited = HashSet::new();
+        lookup.add_theme(theme.unwrap_or_else(current_theme_name), &mut visited);
+        lookup.add_theme("hicolor".into(), &mut visited);
+        lookup.load_index();
+        lookup
+    }
+
+    /// adds a theme followed by its parents, depth first
+    fn add_theme(&mut self, name: String, visited: &mut HashSet<String>) {
+        if !visited.insert(name.clone()) {
+            return;
+        }
+        let index = self
+            .base_dirs
+            .iter()
+            .find_map(|bd| fs::read_to_string(bd.join(&name).join("index.theme")).ok());
+        let Some(index) = index else {
+            return;
+        };
+        let theme = IconTheme::parse(&index);
+        let parents = theme.inherits.clone();
+        self.themes.push((name, theme));
+        for parent in parents {
+            self.add_theme(parent, visited);
+        }
+    }
+
+    /// the directories making up the index, along with the theme and theme dir they belong to
+    fn indexed_dirs(&self) -> Vec<(Option<(usize, usize)>, PathBuf)> {
+        let mut dirs = vec![];
+        for (ti, (theme_name, theme)) in self.themes.iter().enumerate() {
+            for (di, dir) in theme.dirs.iter().enumerate() {
+                for bd in &self.base_dirs {
+                    dirs.push((Some((ti, di)), bd.join(theme_name).join(&dir.subdir)));
+                }
+            }
+        }
+        dirs.extend(self.base_dirs.iter().map(|bd| (None, bd.clone())));
+        dirs
+    }
+
+    fn build_index(
+        &self,
+        dirs: Vec<(Option<(usize, usize)>, PathBuf)>,
+        dir_mtimes: &[(PathBuf, u64)],
+    ) -> IconIndex {
+        let mut index = IconIndex {
+            version: INDEX_VERSION,
+            dir_mtimes: dir_mtimes.to_vec(),
+            ..Default::default()
+        };
+        for (owner, dir) in dirs {
+            for (name, path) in list_icons(&dir) {
+                match owner {
+                    Some((theme, dir)) => {
+                        index
+                            .icons
+                            .entry(name)
+                            .or_default()
+                            .push(Candidate { theme, dir, path });
+                    }
+                    None => {
+                        index.fallback.entry(name).or_insert(path);
+                    }
+                }
             }
         }
+        index
+    }
+
+    /// uses the cached index if no indexed directory has changed, rebuilding it otherwise
+    fn load_index(&mut self) {
+        let dirs = self.indexed_dirs();
+        let dir_mtimes: Vec<_> = dirs.iter().map(|(_, d)| (d.clone(), mtime(d))).collect();
+        self.index = load_cache(
+            &self.cache_path,
+            "icon index",
+            |cached: &IconIndex| cached.version == INDEX_VERSION && cached.dir_mtimes == dir_mtimes,
+            || self.build_index(dirs, &dir_mtimes),
+        );
+    }
+
+    /// Finds the icon file closest to the requested size (in logical pixels) and scale, names
+    /// are matched exactly, but absolute paths (allowed in desktop files) are accepted directly.
+    pub fn find_icon(&self, name: &str, size: u32, scale: u32) -> Option<PathBuf> {
+        let path = Path::new(name);
+        if path.is_absolute() {
+            return path.is_file().then(|| path.to_owned());
+        }
+        let candidates = self.index.icons.get(name).map(Vec::as_slice);
+        let themed = self.themes.iter().enumerate().find_map(|(ti, (_, theme))| {
+            let in_theme = || {
+                candidates
+                    .unwrap_or_default()
+                    .iter()
+                    .filter(move |c| c.theme == ti)
+            };
+            let exact = in_theme().find(|c| {
+                theme
+                    .dirs
+                    .get(c.dir)
+                    .is_some_and(|dir| dir.matches_size(size, scale))
+            });
+            exact
+                .or_else(|| {
+                    in_theme().min_by_key(|c| {
+                        theme
+                            .dirs
+                            .get(c.dir)
+                            .map(|dir| dir.size_distance(size, scale))
+                            .unwrap_or(u32::MAX)
+                    })
+                })
+                .map(|c| c.path.clone())
+        });
+        themed.or_else(|| self.index.fallback.get(name).cloned())
     }
-    res
 }
 
-/// retrieve more than one icons in a single directory walk
-pub fn find_icons(icon_names: Vec<String>) -> HashMap<String, DirEntry> {
-    find_files(icons_iterator(), icon_names)
+#[test]
+fn find_icon_test() {
+    // a theme inheriting from another, hicolor and an unthemed icon, as empty files
+    let root = env::temp_dir().join(format!("searchthing-icon-test-{}", std::process::id()));
+    let icons = root.join("icons");
+    let files = [
+        (
+            "Child/index.theme",
+            "[Icon Theme]\nInherits=Parent\nDirectories=16x16/apps,48x48/apps\n\
+             [16x16/apps]\nSize=16\nType=Fixed\n[48x48/apps]\nSize=48\nType=Fixed\n",
+        ),
+        ("Child/16x16/apps/app.png", ""),
+        ("Child/48x48/apps/app.png", ""),
+        (
+            "Parent/index.theme",
+            "[Icon Theme]\nDirectories=scalable/apps\n\
+             [scalable/apps]\nSize=64\nMinSize=8\nMaxSize=512\nType=Scalable\n",
+        ),
+        ("Parent/scalable/apps/inherited.svg", ""),
+        (
+            "hicolor/index.theme",
+            "[Icon Theme]\nDirectories=48x48/apps\n[48x48/apps]\nSize=48\nType=Fixed\n",
+        ),
+        ("hicolor/48x48/apps/app.png", ""),
+        ("hicolor/48x48/apps/basic.png", ""),
+        ("unthemed.png", ""),
+    ];
+    for (path, content) in files {
+        let path = icons.join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+    let cache_path = root.join("cache/icon_index.json");
+    let lookup = || {
+        IconLookup::with_dirs(
+            Some("Child".into()),
+            vec![icons.clone()],
+            cache_path.clone(),
+        )
+    };
+    let find = |lookup: &IconLookup, name: &str, size: u32| {
+        let path = lookup.find_icon(name, size, 1)?;
+        Some(path.strip_prefix(&icons).unwrap().to_owned())
+    };
+    let path = |path: &str| Some(PathBuf::from(path));
+
+    let built = lookup();
+    assert!(cache_path.is_file());
+    // the cached index is used the second time round
+    for lookup in [built, lookup()] {
+        assert_eq!(find(&lookup, "app", 16), path("Child/16x16/apps/app.png"));
+        assert_eq!(find(&lookup, "app", 48), path("Child/48x48/apps/app.png"));
+        // the closest size, before the parent themes
+        assert_eq!(find(&lookup, "app", 40), path("Child/48x48/apps/app.png"));
+        assert_eq!(
+            find(&lookup, "inherited", 48),
+            path("Parent/scalable/apps/inherited.svg")
+        );
+        assert_eq!(
+            find(&lookup, "basic", 48),
+            path("hicolor/48x48/apps/basic.png")
+        );
+        assert_eq!(find(&lookup, "unthemed", 48), path("unthemed.png"));
+        assert_eq!(find(&lookup, "missing", 48), None);
+    }
+    fs::remove_dir_all(root).unwrap();
 }
@@ -0,0 +1,151 @@
+//! Keybindings, from key chords to [KeyCommand]s.
+
+use egui_inspect::{
+    egui::{Event, InputState, Key, Modifiers},
+    logging::log::warn,
+};
+use searchthing_core::KeyCommand;
+use std::collections::HashMap;
+
+const DEFAULT_BINDINGS: &[(&str, &str)] = &[
+    ("Up", "prev"),
+    ("Down", "next"),
+    ("Ctrl+P", "prev"),
+    ("Ctrl+N", "next"),
+    ("Ctrl+K", "prev"),
+    ("Ctrl+J", "next"),
+    ("PageUp", "page-up"),
+    ("PageDown", "page-down"),
+    ("Home", "first"),
+    ("End", "last"),
+    ("Shift+Tab", "prev-module"),
+    ("Tab", "next-module"),
+    ("Alt+1", "pick-1"),
+    ("Alt+2", "pick-2"),
+    ("Alt+3", "pick-3"),
+    ("Alt+4", "pick-4"),
+    ("Alt+5", "pick-5"),
+    ("Alt+6", "pick-6"),
+    ("Alt+7", "pick-7"),
+    ("Alt+8", "pick-8"),
+    ("Alt+9", "pick-9"),
+    ("Enter", "select"),
+    ("Ctrl+Enter", "select-stay-open"),
+    ("Shift+Enter", "secondary-action"),
+    ("Alt+Enter", "accept-custom"),
+    ("Escape", "exit"),
+    ("F3", "toggle-preview"),
+    ("Ctrl+R", "cycle-match-mode"),
+    ("Ctrl+A", "line-start"),
+    ("Ctrl+E", "line-end"),
+    ("Ctrl+U", "delete-to-start"),
+    ("Ctrl+W", "delete-word"),
+];
+
+/// bindings over the defaults in multi-select mode, Shift+Enter otherwise being the secondary
+/// action. Not plain Space, which is still typed into the search input (unless bound to it).
+/// NOTE: most terminals report Shift+Enter and Shift+Space without the Shift, Ctrl+Space is the
+/// one the TUI can tell apart
+pub const MULTI_SELECT_BINDINGS: &[(&str, &str)] = &[
+    ("Shift+Enter", "toggle-mark"),
+    ("Shift+Space", "toggle-mark"),
+    ("Ctrl+Space", "toggle-mark"),
+];
+
+/// parses a chord like "Ctrl+Shift+K"
+fn parse_chord(chord: &str) -> Option<(Modifiers, Key)> {
+    let (modifier_names, key_name) = match chord.rsplit_once('+') {
+        // "Ctrl++" binds the plus key
+        Some((rest, "")) => (rest.strip_suffix('+')?, "+"),
+        Some((modifiers, key)) => (modifiers, key),
+        None => ("", chord),
+    };
+    let mut modifiers = Modifiers::NONE;
+    for name in modifier_names.split('+').filter(|n| !n.is_empty()) {
+        modifiers |= match name.to_lowercase().as_str() {
+            "ctrl" | "control" => Modifiers::CTRL,
+            "shift" => Modifiers::SHIFT,
+            "alt" => Modifiers::ALT,
+            _ => return None,
+        };
+    }
+    Some((modifiers, Key::from_name(key_name)?))
+}
+
+#[test]
+fn parse_chord_test() {
+    assert_eq!(parse_chord("Down"), Some((Modifiers::NONE, Key::ArrowDown)));
+    assert_eq!(
+        parse_chord("ctrl+shift+k"),
+        Some((Modifiers::CTRL | Modifiers::SHIFT, Key::K))
+    );
+    assert_eq!(parse_chord("Alt++"), Some((Modifiers::ALT, Key::Plus)));
+    assert_eq!(parse_chord("Hyper+K"), None);
+    assert_eq!(KeyCommand::from_name("pick-3"), Some(KeyCommand::Pick(2)));
+    assert_eq!(KeyCommand::from_name("pick-0"), None);
+    assert_eq!(
+        KeyCommand::from_name("custom-19"),
+        Some(KeyCommand::Custom(19))
+    );
+    assert_eq!(KeyCommand::from_name("custom-20"), None);
+}
+
+pub struct Keybindings {
+    /// most specific chords first, as shift and alt are otherwise ignored when matching
+    bindings: Vec<(Modifiers, Key, KeyCommand)>,
+}
+
+impl Keybindings {
+    /// the default bindings, changed by a table of chords to command names (or "none" to unbind)
+    pub fn new(overrides: &HashMap<String, String>) -> Self {
+        let mut table = HashMap::new();
+        let defaults = DEFAULT_BINDINGS.iter().map(|(c, n)| (*c, *n));
+        let overrides = overrides.iter().map(|(c, n)| (c.as_str(), n.as_str()));
+        for (chord, name) in defaults.chain(overrides) {
+            let Some(chord) = parse_chord(chord) else {
+                warn!("Unknown key chord {chord:?} in keybindings, ignoring it");
+                continue;
+            };
+            if name == "none" {
+                table.remove(&chord);
+                continue;
+            }
+            match KeyCommand::from_name(name) {
+                Some(command) => {
+                    table.insert(chord, command);
+                }
+                None => warn!("Unknown command {name:?} in keybindings, ignoring it"),
+            }
+        }
+        let mut bindings: Vec<_> = table
+            .into_iter()
+            .map(|((modifiers, key), command)| (modifiers, key, command))
+            .collect();
+        let specificity = |m: &Modifiers| [m.ctrl, m.shift, m.alt].iter().filter(|b| **b).count();
+        bindings.sort_by_key(|(m, _, _)| std::cmp::Reverse(specificity(m)));
+        Self { bindings }
+    }
+
+    /// Takes the bound key presses out of the input, so that they do not reach the search input.
+    /// Held keys produce repeated presses, which are all returned.
+    pub fn consume_commands(&self, input: &mut InputState) -> Vec<KeyCommand> {
+        let mut commands = vec![];
+        for (modifiers, key, command) in &self.bindings {
+            let count = input.count_and_consume_key(*modifiers, *key);
+            commands.extend(std::iter::repeat_n(*command, count));
+            // NOTE: the search input types from text events, which a bound space also produces
+            if *key == Key::Space && count > 0 && !modifiers.ctrl && !modifiers.alt {
+                input.events.retain(|e| *e != Event::Text(" ".into()));
+            }
+        }
+        commands
+    }
+
+    /// the command bound to an exact chord, for frontends without an egui input state
+    pub fn command(&self, modifiers: Modifiers, key: Key) -> Option<KeyCommand> {
+        self.bindings
+            .iter()
+            .find(|(m, k, _)| *m == modifiers && *k == key)
+            .map(|(_, _, command)| *command)
+    }
+}
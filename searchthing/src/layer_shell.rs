@@ -0,0 +1,789 @@
+//! Presents the UI as a `wlr-layer-shell` overlay surface, with exclusive keyboard focus, rather
+//! than as a normal (possibly tiled) window. Used with `--layer-shell` when built with the
+//! `layer-shell` feature, on compositors implementing the protocol (e.g. sway, hyprland, river).
+//!
+//! Can be tried without a session through a headless wlroots compositor, e.g.
+//! `WLR_BACKENDS=headless WLR_RENDERER=pixman sway`, with `WAYLAND_DISPLAY` set to its socket. CI
+//! only builds it and tests the pure parts, e.g. [layer_placement].
+
+use crate::{
+    geometry::{Anchor, WindowConfig},
+    SearchThing,
+};
+use egui_inspect::{
+    egui::{
+        self, pos2, vec2, Event, Key, MouseWheelUnit, PointerButton, Pos2, Rect, Vec2,
+        ViewportCommand, ViewportId,
+    },
+    logging::log::warn,
+};
+use glutin::{
+    config::{ConfigTemplateBuilder, GlConfig},
+    context::{ContextAttributesBuilder, NotCurrentGlContext, PossiblyCurrentContext},
+    display::{Display, DisplayApiPreference, GlDisplay},
+    surface::{GlSurface, Surface, SurfaceAttributesBuilder, SwapInterval, WindowSurface},
+};
+use raw_window_handle::{
+    RawDisplayHandle, RawWindowHandle, WaylandDisplayHandle, WaylandWindowHandle,
+};
+use smithay_client_toolkit::{
+    compositor::{CompositorHandler, CompositorState},
+    delegate_compositor, delegate_keyboard, delegate_layer, delegate_output, delegate_pointer,
+    delegate_registry, delegate_seat,
+    output::{OutputHandler, OutputState},
+    reexports::{
+        calloop::{
+            channel::{self, Channel},
+            EventLoop, LoopHandle,
+        },
+        calloop_wayland_source::WaylandSource,
+        client::{
+            globals::registry_queue_init,
+            protocol::{wl_keyboard, wl_output, wl_pointer, wl_seat, wl_surface},
+            Connection, EventQueue, Proxy, QueueHandle,
+        },
+    },
+    registry::{ProvidesRegistryState, RegistryState},
+    registry_handlers,
+    seat::{
+        keyboard::{KeyEvent, KeyboardHandler, Keysym, Modifiers},
+        pointer::{
+            PointerEvent, PointerEventKind, PointerHandler, BTN_LEFT, BTN_MIDDLE, BTN_RIGHT,
+        },
+        Capability, SeatHandler, SeatState,
+    },
+    shell::{
+        wlr_layer::{
+            Anchor as LayerAnchor, KeyboardInteractivity, Layer, LayerShell, LayerShellHandler,
+            LayerSurface, LayerSurfaceConfigure,
+        },
+        WaylandSurface,
+    },
+};
+use std::{
+    ffi::c_void,
+    num::NonZeroU32,
+    ptr::NonNull,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+/// A connection to a compositor which supports the layer shell protocol.
+pub struct LayerShellBackend {
+    conn: Connection,
+    event_queue: EventQueue<LayerShellState>,
+    state: LayerShellState,
+}
+
+/// The GL surface and the egui painter drawing to it.
+struct Gl {
+    surface: Surface<WindowSurface>,
+    context: PossiblyCurrentContext,
+    painter: egui_glow::Painter,
+}
+
+struct LayerShellState {
+    registry_state: RegistryState,
+    seat_state: SeatState,
+    output_state: OutputState,
+    compositor_state: CompositorState,
+    layer_shell: LayerShell,
+    layer: Option<LayerSurface>,
+    keyboard: Option<wl_keyboard::WlKeyboard>,
+    pointer: Option<wl_pointer::WlPointer>,
+    loop_handle: Option<LoopHandle<'static, Self>>,
+    gl: Option<Gl>,
+    /// logical size of the surface
+    size: (u32, u32),
+    scale: i32,
+    monitor_size: Option<Vec2>,
+    exit: bool,
+    needs_redraw: bool,
+    repaint_at: Option<Instant>,
+    start: Instant,
+    events: Vec<Event>,
+    modifiers: egui::Modifiers,
+    keyboard_focus: bool,
+}
+
+fn layer_anchor(anchor: Anchor) -> LayerAnchor {
+    match anchor {
+        // NOTE: no global pointer position on wayland, so centred like an unanchored surface
+        Anchor::Center | Anchor::Cursor => LayerAnchor::empty(),
+        Anchor::Top => LayerAnchor::TOP,
+        Anchor::Bottom => LayerAnchor::BOTTOM,
+        Anchor::Left => LayerAnchor::LEFT,
+        Anchor::Right => LayerAnchor::RIGHT,
+        Anchor::TopLeft => LayerAnchor::TOP | LayerAnchor::LEFT,
+        Anchor::TopRight => LayerAnchor::TOP | LayerAnchor::RIGHT,
+        Anchor::BottomLeft => LayerAnchor::BOTTOM | LayerAnchor::LEFT,
+        Anchor::BottomRight => LayerAnchor::BOTTOM | LayerAnchor::RIGHT,
+    }
+}
+
+/// the anchor and the (top, right, bottom, left) margins of the surface, a margin only on the
+/// anchored edges
+fn layer_placement(window: &WindowConfig) -> (LayerAnchor, [i32; 4]) {
+    let anchor = layer_anchor(window.anchor);
+    let edges = [
+        LayerAnchor::TOP,
+        LayerAnchor::RIGHT,
+        LayerAnchor::BOTTOM,
+        LayerAnchor::LEFT,
+    ];
+    let margins = edges.map(|edge| match anchor.contains(edge) {
+        true => window.margin,
+        false => 0,
+    });
+    (anchor, margins)
+}
+
+#[test]
+fn layer_placement_test() {
+    let placement = |anchor, margin| {
+        layer_placement(&WindowConfig {
+            anchor,
+            margin,
+            ..Default::default()
+        })
+    };
+    assert_eq!(
+        placement(Anchor::Center, 10),
+        (LayerAnchor::empty(), [0; 4])
+    );
+    assert_eq!(
+        placement(Anchor::Cursor, 10),
+        (LayerAnchor::empty(), [0; 4])
+    );
+    assert_eq!(
+        placement(Anchor::Top, 10),
+        (LayerAnchor::TOP, [10, 0, 0, 0])
+    );
+    assert_eq!(
+        placement(Anchor::BottomLeft, 4),
+        (LayerAnchor::BOTTOM | LayerAnchor::LEFT, [0, 0, 4, 4])
+    );
+    assert_eq!(
+        placement(Anchor::TopRight, 0),
+        (LayerAnchor::TOP | LayerAnchor::RIGHT, [0; 4])
+    );
+}
+
+impl LayerShellBackend {
+    /// fails when not on wayland, or the compositor does not implement the protocol
+    pub fn connect() -> Result<Self, String> {
+        let conn = Connection::connect_to_env().map_err(|e| e.to_string())?;
+        let (globals, event_queue) = registry_queue_init(&conn).map_err(|e| e.to_string())?;
+        let qh = event_queue.handle();
+        let layer_shell = LayerShell::bind(&globals, &qh)
+            .map_err(|e| format!("zwlr_layer_shell_v1 is not available ({e})"))?;
+        let compositor_state = CompositorState::bind(&globals, &qh)
+            .map_err(|e| format!("wl_compositor is not available ({e})"))?;
+        let state = LayerShellState {
+            registry_state: RegistryState::new(&globals),
+            seat_state: SeatState::new(&globals, &qh),
+            output_state: OutputState::new(&globals, &qh),
+            compositor_state,
+            layer_shell,
+            layer: None,
+            keyboard: None,
+            pointer: None,
+            loop_handle: None,
+            gl: None,
+            size: (0, 0),
+            scale: 1,
+            monitor_size: None,
+            exit: false,
+            needs_redraw: false,
+            repaint_at: None,
+            start: Instant::now(),
+            events: vec![],
+            modifiers: Default::default(),
+            keyboard_focus: false,
+        };
+        Ok(Self {
+            conn,
+            event_queue,
+            state,
+        })
+    }
+
+    /// runs until a selection is made or the surface is closed
+    pub fn run(
+        mut self,
+        window: &WindowConfig,
+        make_app: impl FnOnce(&egui::Context) -> SearchThing,
+    ) -> Result<(), String> {
+        let state = &mut self.state;
+        let qh = self.event_queue.handle();
+        // NOTE: outputs are announced on the first roundtrip, and described on the second
+        for _ in 0..2 {
+            self.event_queue
+                .roundtrip(state)
+                .map_err(|e| e.to_string())?;
+        }
+        state.monitor_size = state.output_state.outputs().find_map(|output| {
+            let (w, h) = state.output_state.info(&output)?.logical_size?;
+            Some(vec2(w as f32, h as f32))
+        });
+        let size = match state.monitor_size {
+            Some(monitor) => window.size(monitor),
+            None => window.initial_size(),
+        };
+        state.size = (size.x as u32, size.y as u32);
+
+        let surface = state.compositor_state.create_surface(&qh);
+        let layer = state.layer_shell.create_layer_surface(
+            &qh,
+            surface,
+            Layer::Overlay,
+            Some("searchthing"),
+            None,
+        );
+        let (anchor, [top, right, bottom, left]) = layer_placement(window);
+        layer.set_anchor(anchor);
+        layer.set_margin(top, right, bottom, left);
+        layer.set_keyboard_interactivity(KeyboardInteractivity::Exclusive);
+        layer.set_size(state.size.0, state.size.1);
+        // NOTE: an initial commit without a buffer, the compositor then sends the first configure
+        layer.commit();
+        state.layer = Some(layer);
+
+        let mut event_loop: EventLoop<LayerShellState> =
+            EventLoop::try_new().map_err(|e| e.to_string())?;
+        let loop_handle = event_loop.handle();
+        WaylandSource::new(self.conn.clone(), self.event_queue)
+            .insert(loop_handle.clone())
+            .map_err(|e| e.to_string())?;
+        state.loop_handle = Some(loop_handle.clone());
+
+        let ctx = egui::Context::default();
+        // repaints requested by other threads (e.g. icon loading), or after a delay
+        let (repaint_tx, repaint_rx): (_, Channel<Duration>) = channel::channel();
+        ctx.set_request_repaint_callback(move |info| {
+            let _ = repaint_tx.send(info.delay);
+        });
+        loop_handle
+            .insert_source(repaint_rx, |event, _, state| {
+                if let channel::Event::Msg(delay) = event {
+                    state.schedule_repaint(delay);
+                }
+            })
+            .map_err(|e| e.to_string())?;
+        let mut app = make_app(&ctx);
+
+        while !self.state.exit {
+            let timeout =
+                (self.state.repaint_at).map(|at| at.saturating_duration_since(Instant::now()));
+            event_loop
+                .dispatch(timeout, &mut self.state)
+                .map_err(|e| e.to_string())?;
+            if (self.state.repaint_at).is_some_and(|at| at <= Instant::now()) {
+                self.state.repaint_at = None;
+                self.state.needs_redraw = true;
+            }
+            if self.state.needs_redraw && self.state.gl.is_some() {
+                self.state.needs_redraw = false;
+                self.state.draw(&ctx, &mut app)?;
+            }
+        }
+        if let Some(gl) = &mut self.state.gl {
+            gl.painter.destroy();
+        }
+        Ok(())
+    }
+}
+
+impl LayerShellState {
+    fn layer(&self) -> &LayerSurface {
+        self.layer
+            .as_ref()
+            .expect("layer surface created before dispatching")
+    }
+
+    fn schedule_repaint(&mut self, delay: Duration) {
+        let at = Instant::now() + delay;
+        self.repaint_at = Some(self.repaint_at.map_or(at, |t| t.min(at)));
+    }
+
+    fn physical_size(&self) -> (u32, u32) {
+        let scale = self.scale.max(1) as u32;
+        (self.size.0 * scale, self.size.1 * scale)
+    }
+
+    fn create_gl(&mut self) -> Result<(), String> {
+        let display_ptr = self.layer().wl_surface().backend().upgrade();
+        let display_ptr = display_ptr
+            .ok_or("wayland connection closed")?
+            .display_ptr() as *mut c_void;
+        let surface_ptr = self.layer().wl_surface().id().as_ptr() as *mut c_void;
+        let raw_display = RawDisplayHandle::Wayland(WaylandDisplayHandle::new(
+            NonNull::new(display_ptr).ok_or("null wl_display")?,
+        ));
+        let raw_window = RawWindowHandle::Wayland(WaylandWindowHandle::new(
+            NonNull::new(surface_ptr).ok_or("null wl_surface")?,
+        ));
+        let (w, h) = self.physical_size();
+        let size = |v: u32| NonZeroU32::new(v.max(1)).unwrap();
+        // SAFETY: the display and surface outlive the GL objects, which are dropped with the state
+        unsafe {
+            let display =
+                Display::new(raw_display, DisplayApiPreference::Egl).map_err(|e| e.to_string())?;
+            let template = ConfigTemplateBuilder::new()
+                .with_alpha_size(8)
+                .with_transparency(true)
+                .build();
+            let config = display
+                .find_configs(template)
+                .map_err(|e| e.to_string())?
+                .max_by_key(|c| c.num_samples())
+                .ok_or("no EGL config found")?;
+            let surface_attributes = SurfaceAttributesBuilder::<WindowSurface>::new().build(
+                raw_window,
+                size(w),
+                size(h),
+            );
+            let surface = display
+                .create_window_surface(&config, &surface_attributes)
+                .map_err(|e| e.to_string())?;
+            let context_attributes = ContextAttributesBuilder::new().build(Some(raw_window));
+            let context = display
+                .create_context(&config, &context_attributes)
+                .map_err(|e| e.to_string())?
+                .make_current(&surface)
+                .map_err(|e| e.to_string())?;
+            // NOTE: not waiting on frame callbacks, repaints are driven by egui
+            if let Err(why) = surface.set_swap_interval(&context, SwapInterval::DontWait) {
+                warn!("Failed to set the swap interval: {why}");
+            }
+            let gl = glow::Context::from_loader_function_cstr(|s| display.get_proc_address(s));
+            let painter = egui_glow::Painter::new(Arc::new(gl), "", None, false)
+                .map_err(|e| e.to_string())?;
+            self.gl = Some(Gl {
+                surface,
+                context,
+                painter,
+            });
+        }
+        Ok(())
+    }
+
+    fn resize_gl(&mut self) {
+        let (w, h) = self.physical_size();
+        if let Some(gl) = &self.gl {
+            let size = |v: u32| NonZeroU32::new(v.max(1)).unwrap();
+            gl.surface.resize(&gl.context, size(w), size(h));
+        }
+    }
+
+    fn draw(&mut self, ctx: &egui::Context, app: &mut SearchThing) -> Result<(), String> {
+        let ppp = self.scale.max(1) as f32;
+        let screen_rect =
+            Rect::from_min_size(Pos2::ZERO, vec2(self.size.0 as f32, self.size.1 as f32));
+        let mut raw_input = egui::RawInput {
+            screen_rect: Some(screen_rect),
+            time: Some(self.start.elapsed().as_secs_f64()),
+            modifiers: self.modifiers,
+            events: std::mem::take(&mut self.events),
+            focused: self.keyboard_focus,
+            ..Default::default()
+        };
+        let viewport = raw_input.viewports.entry(ViewportId::ROOT).or_default();
+        viewport.native_pixels_per_point = Some(ppp);
+        viewport.monitor_size = self.monitor_size;
+        viewport.inner_rect = Some(screen_rect);
+        viewport.focused = Some(self.keyboard_focus);
+
+        let output = ctx.run(raw_input, |ctx| app.show(ctx));
+        if let Some(viewport_output) = output.viewport_output.get(&ViewportId::ROOT) {
+            for command in &viewport_output.commands {
+                match command {
+                    ViewportCommand::Close => self.exit = true,
+                    ViewportCommand::InnerSize(size) => {
+                        self.layer().set_size(size.x as u32, size.y as u32);
+                        self.layer().commit();
+                    }
+                    // NOTE: placement is through the anchor and margins instead
+                    _ => {}
+                }
+            }
+            if viewport_output.repaint_delay != Duration::MAX {
+                self.schedule_repaint(viewport_output.repaint_delay);
+            }
+        }
+
+        let (w, h) = self.physical_size();
+        let gl = self.gl.as_mut().ok_or("drawing before configure")?;
+        let primitives = ctx.tessellate(output.shapes, output.pixels_per_point);
+        gl.painter.clear([w, h], [0.0; 4]);
+        gl.painter.paint_and_update_textures(
+            [w, h],
+            output.pixels_per_point,
+            &primitives,
+            &output.textures_delta,
+        );
+        gl.surface
+            .swap_buffers(&gl.context)
+            .map_err(|e| e.to_string())
+    }
+
+    fn key_event(&mut self, event: &KeyEvent, pressed: bool, repeat: bool) {
+        if let Some(key) = egui_key(event.keysym) {
+            self.events.push(Event::Key {
+                key,
+                physical_key: None,
+                pressed,
+                repeat,
+                modifiers: self.modifiers,
+            });
+        }
+        let typing = !(self.modifiers.ctrl || self.modifiers.alt);
+        if let (true, true, Some(text)) = (pressed, typing, &event.utf8) {
+            if !text.chars().any(char::is_control) {
+                self.events.push(Event::Text(text.clone()));
+            }
+        }
+        self.needs_redraw = true;
+    }
+}
+
+/// maps the keys egui knows of, letters and digits through their (unshifted) character
+fn egui_key(keysym: Keysym) -> Option<Key> {
+    Some(match keysym {
+        Keysym::Return | Keysym::KP_Enter => Key::Enter,
+        Keysym::Escape => Key::Escape,
+        Keysym::Tab | Keysym::ISO_Left_Tab => Key::Tab,
+        Keysym::BackSpace => Key::Backspace,
+        Keysym::Delete => Key::Delete,
+        Keysym::Insert => Key::Insert,
+        Keysym::Home => Key::Home,
+        Keysym::End => Key::End,
+        Keysym::Page_Up => Key::PageUp,
+        Keysym::Page_Down => Key::PageDown,
+        Keysym::Left => Key::ArrowLeft,
+        Keysym::Right => Key::ArrowRight,
+        Keysym::Up => Key::ArrowUp,
+        Keysym::Down => Key::ArrowDown,
+        Keysym::space => Key::Space,
+        Keysym::F1 => Key::F1,
+        Keysym::F2 => Key::F2,
+        Keysym::F3 => Key::F3,
+        Keysym::F4 => Key::F4,
+        Keysym::F5 => Key::F5,
+        Keysym::F6 => Key::F6,
+        Keysym::F7 => Key::F7,
+        Keysym::F8 => Key::F8,
+        Keysym::F9 => Key::F9,
+        Keysym::F10 => Key::F10,
+        Keysym::F11 => Key::F11,
+        Keysym::F12 => Key::F12,
+        _ => return Key::from_name(&keysym.key_char()?.to_string()),
+    })
+}
+
+impl CompositorHandler for LayerShellState {
+    fn scale_factor_changed(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        surface: &wl_surface::WlSurface,
+        new_factor: i32,
+    ) {
+        self.scale = new_factor;
+        surface.set_buffer_scale(new_factor);
+        self.resize_gl();
+        self.needs_redraw = true;
+    }
+
+    fn transform_changed(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        _surface: &wl_surface::WlSurface,
+        _new_transform: wl_output::Transform,
+    ) {
+    }
+
+    fn frame(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        _surface: &wl_surface::WlSurface,
+        _time: u32,
+    ) {
+    }
+
+    fn surface_enter(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        _surface: &wl_surface::WlSurface,
+        output: &wl_output::WlOutput,
+    ) {
+        if let Some((w, h)) = self.output_state.info(output).and_then(|i| i.logical_size) {
+            self.monitor_size = Some(vec2(w as f32, h as f32));
+        }
+    }
+
+    fn surface_leave(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        _surface: &wl_surface::WlSurface,
+        _output: &wl_output::WlOutput,
+    ) {
+    }
+}
+
+impl OutputHandler for LayerShellState {
+    fn output_state(&mut self) -> &mut OutputState {
+        &mut self.output_state
+    }
+
+    fn new_output(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        _output: wl_output::WlOutput,
+    ) {
+    }
+
+    fn update_output(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        _output: wl_output::WlOutput,
+    ) {
+    }
+
+    fn output_destroyed(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        _output: wl_output::WlOutput,
+    ) {
+    }
+}
+
+impl LayerShellHandler for LayerShellState {
+    fn closed(&mut self, _conn: &Connection, _qh: &QueueHandle<Self>, _layer: &LayerSurface) {
+        self.exit = true;
+    }
+
+    fn configure(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        _layer: &LayerSurface,
+        configure: LayerSurfaceConfigure,
+        _serial: u32,
+    ) {
+        // NOTE: zero means the size is left to us, keeping the requested one
+        let (w, h) = configure.new_size;
+        if w > 0 && h > 0 {
+            self.size = (w, h);
+        }
+        if self.gl.is_none() {
+            if let Err(why) = self.create_gl() {
+                warn!("Failed to set up drawing to the layer surface: {why}");
+                self.exit = true;
+                return;
+            }
+        } else {
+            self.resize_gl();
+        }
+        self.needs_redraw = true;
+    }
+}
+
+impl SeatHandler for LayerShellState {
+    fn seat_state(&mut self) -> &mut SeatState {
+        &mut self.seat_state
+    }
+
+    fn new_seat(&mut self, _: &Connection, _: &QueueHandle<Self>, _: wl_seat::WlSeat) {}
+
+    fn new_capability(
+        &mut self,
+        _conn: &Connection,
+        qh: &QueueHandle<Self>,
+        seat: wl_seat::WlSeat,
+        capability: Capability,
+    ) {
+        if capability == Capability::Keyboard && self.keyboard.is_none() {
+            let Some(loop_handle) = self.loop_handle.clone() else {
+                return;
+            };
+            let keyboard = self.seat_state.get_keyboard_with_repeat(
+                qh,
+                &seat,
+                None,
+                loop_handle,
+                Box::new(|state, _keyboard, event| state.key_event(&event, true, true)),
+            );
+            match keyboard {
+                Ok(keyboard) => self.keyboard = Some(keyboard),
+                Err(why) => warn!("Failed to get the seat keyboard: {why}"),
+            }
+        }
+        if capability == Capability::Pointer && self.pointer.is_none() {
+            match self.seat_state.get_pointer(qh, &seat) {
+                Ok(pointer) => self.pointer = Some(pointer),
+                Err(why) => warn!("Failed to get the seat pointer: {why}"),
+            }
+        }
+    }
+
+    fn remove_capability(
+        &mut self,
+        _conn: &Connection,
+        _: &QueueHandle<Self>,
+        _: wl_seat::WlSeat,
+        capability: Capability,
+    ) {
+        if capability == Capability::Keyboard {
+            if let Some(keyboard) = self.keyboard.take() {
+                keyboard.release();
+            }
+        }
+        if capability == Capability::Pointer {
+            if let Some(pointer) = self.pointer.take() {
+                pointer.release();
+            }
+        }
+    }
+
+    fn remove_seat(&mut self, _: &Connection, _: &QueueHandle<Self>, _: wl_seat::WlSeat) {}
+}
+
+impl KeyboardHandler for LayerShellState {
+    fn enter(
+        &mut self,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+        _: &wl_keyboard::WlKeyboard,
+        _surface: &wl_surface::WlSurface,
+        _: u32,
+        _: &[u32],
+        _: &[Keysym],
+    ) {
+        self.keyboard_focus = true;
+        self.needs_redraw = true;
+    }
+
+    fn leave(
+        &mut self,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+        _: &wl_keyboard::WlKeyboard,
+        _surface: &wl_surface::WlSurface,
+        _: u32,
+    ) {
+        self.keyboard_focus = false;
+        self.needs_redraw = true;
+    }
+
+    fn press_key(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        _: &wl_keyboard::WlKeyboard,
+        _: u32,
+        event: KeyEvent,
+    ) {
+        self.key_event(&event, true, false);
+    }
+
+    fn release_key(
+        &mut self,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+        _: &wl_keyboard::WlKeyboard,
+        _: u32,
+        event: KeyEvent,
+    ) {
+        self.key_event(&event, false, false);
+    }
+
+    fn update_modifiers(
+        &mut self,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+        _: &wl_keyboard::WlKeyboard,
+        _serial: u32,
+        modifiers: Modifiers,
+        _layout: u32,
+    ) {
+        self.modifiers = egui::Modifiers {
+            alt: modifiers.alt,
+            ctrl: modifiers.ctrl,
+            shift: modifiers.shift,
+            mac_cmd: false,
+            command: modifiers.ctrl,
+        };
+    }
+}
+
+impl PointerHandler for LayerShellState {
+    fn pointer_frame(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        _pointer: &wl_pointer::WlPointer,
+        events: &[PointerEvent],
+    ) {
+        for event in events {
+            let pos = pos2(event.position.0 as f32, event.position.1 as f32);
+            match event.kind {
+                PointerEventKind::Enter { .. } | PointerEventKind::Motion { .. } => {
+                    self.events.push(Event::PointerMoved(pos));
+                }
+                PointerEventKind::Leave { .. } => {
+                    self.events.push(Event::PointerGone);
+                }
+                PointerEventKind::Press { button, .. }
+                | PointerEventKind::Release { button, .. } => {
+                    let button = match button {
+                        BTN_LEFT => PointerButton::Primary,
+                        BTN_RIGHT => PointerButton::Secondary,
+                        BTN_MIDDLE => PointerButton::Middle,
+                        _ => continue,
+                    };
+                    self.events.push(Event::PointerButton {
+                        pos,
+                        button,
+                        pressed: matches!(event.kind, PointerEventKind::Press { .. }),
+                        modifiers: self.modifiers,
+                    });
+                }
+                PointerEventKind::Axis {
+                    horizontal,
+                    vertical,
+                    ..
+                } => {
+                    self.events.push(Event::MouseWheel {
+                        unit: MouseWheelUnit::Point,
+                        delta: -vec2(horizontal.absolute as f32, vertical.absolute as f32),
+                        modifiers: self.modifiers,
+                    });
+                }
+            }
+        }
+        self.needs_redraw = true;
+    }
+}
+
+delegate_compositor!(LayerShellState);
+delegate_output!(LayerShellState);
+delegate_seat!(LayerShellState);
+delegate_keyboard!(LayerShellState);
+delegate_pointer!(LayerShellState);
+delegate_layer!(LayerShellState);
+delegate_registry!(LayerShellState);
+
+impl ProvidesRegistryState for LayerShellState {
+    fn registry(&mut self) -> &mut RegistryState {
+        &mut self.registry_state
+    }
+    registry_handlers![OutputState, SeatState];
+}
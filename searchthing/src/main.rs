@@ -1,24 +1,49 @@
-use clap::Parser;
-use dmenu::DmenuModule;
-use fonts::custom_egui_font_def;
+use clap::{Parser, Subcommand};
+use config::config;
+use dmenu::{Column, DmenuModule, DmenuOptions, InputFormat, OutputFormat};
+use fonts::FontSettings;
 use plugin::PluginModule;
-use std::{cell::RefCell, path::PathBuf, process::exit, thread::sleep, time::Duration};
+use searchthing_interface::MatchMode;
+use std::{
+    cell::RefCell, collections::HashMap, path::PathBuf, process::exit, thread::sleep,
+    time::Duration,
+};
 use symbols::SymbolsModule;
 
 use applications::ApplicationsModule;
 use egui_inspect::{
-    eframe::{CreationContext, NativeOptions, WindowBuilderHook},
-    egui::{self, Color32, Key, RichText, Stroke, Vec2},
+    eframe::{self, NativeOptions, WindowBuilderHook},
+    egui::{
+        self,
+        text::{CCursor, CCursorRange},
+        text_edit::TextEditState,
+        vec2, Color32, Key, RichText, Stroke, Vec2, ViewportCommand,
+    },
     logging::{log::warn, setup_mixed_logger, FileLogOption},
     search_select::non_contiguous_highlight,
-    EframeMain, EguiInspect, FrameStyle, DEFAULT_FRAME_STYLE,
+    EguiInspect, FrameStyle, DEFAULT_FRAME_STYLE,
 };
-use searchthing_interface::{FuzzySearch, SearchMethod};
-use ui::{AppIconPathCache, WrappedSearcher};
+use geometry::{pointer_position, Anchor, Layout, WindowConfig};
+use icon_loader::AppIconCache;
+use keyboard::{Keybindings, MULTI_SELECT_BINDINGS};
+use searchthing_core::{
+    best_match, edit_line, KeyCommand, Outcome, Selection, Session, WrappedSearcher,
+};
+use ui::{Icon, InspectSearcher};
 
+mod cli;
+mod config;
+mod dmenu_compat;
 mod fonts;
+mod geometry;
+mod icon_loader;
 mod icon_search;
+mod keyboard;
+#[cfg(feature = "layer-shell")]
+mod layer_shell;
+mod tui;
 mod ui;
+mod xdg;
 
 // TODO: Load cliarg defaults from a config file
 
@@ -39,6 +64,53 @@ struct SearchThingArgs {
     /// Options are specified by lines in stdin. The selected option is printed on stdout.
     #[arg(short, long)]
     dmenu: Option<Option<String>>,
+    /// In dmenu mode, what to print for the selected line, as with rofi's -format: s (its text),
+    /// i (its index) or d (its index counting from 1).
+    #[arg(long, value_parser = parse_format, default_value = "s")]
+    format: OutputFormat,
+    /// In dmenu mode, the char that separates options in the input (e.g. '|' or '\0') instead of
+    /// newlines, as with rofi's -sep.
+    #[arg(long, value_parser = parse_separator)]
+    separator: Option<u8>,
+    /// In dmenu mode, split each line of input into fields by this delimiter (e.g. '\t'), given in
+    /// the order of --columns.
+    #[arg(long)]
+    delimiter: Option<String>,
+    /// The order of delimited fields, of name, desc, icon and output (printed when selected,
+    /// instead of the name). Without an output column, the name field alone is printed, not the
+    /// whole line.
+    #[arg(
+        long,
+        value_delimiter = ',',
+        value_parser = parse_column,
+        default_value = "name,desc,icon,output"
+    )]
+    columns: Vec<Column>,
+    /// In dmenu mode, read each line of input as a JSON object with name, desc, icon and output
+    /// (printed when selected, instead of the name) fields.
+    #[arg(long, conflicts_with = "delimiter")]
+    json_lines: bool,
+    /// Highlight this match (counting from 0) initially.
+    #[arg(long)]
+    selected_row: Option<usize>,
+    /// A message shown beneath the search input.
+    #[arg(long)]
+    message: Option<String>,
+    /// In dmenu mode, only allow selecting one of the options, rather than also the search text as
+    /// is (when it matches nothing, or with accept-custom).
+    #[arg(long)]
+    no_custom: bool,
+    /// Allow marking several matches with Shift+Enter, Shift+Space or Ctrl+Space, to select them all at once
+    /// (e.g. to print several dmenu options).
+    #[arg(long)]
+    multi_select: bool,
+    /// In dmenu mode, end each printed option with a null byte rather than a newline.
+    #[arg(short = '0', long)]
+    print0: bool,
+    /// Bind a key chord to a command, over the config file, e.g. --bind Alt+1=custom-1. May be
+    /// provided multiple times.
+    #[arg(long, value_parser)]
+    bind: Vec<String>,
     /// Unicode symbol picker mode.
     #[arg(short, long)]
     symbols: bool,
@@ -47,73 +119,132 @@ struct SearchThingArgs {
     /// Do an initial search with this text.
     #[arg(short, long)]
     init_search: Option<String>,
-    /// Override the application text font
+    /// Override the application text font, given as a fontconfig style family name or pattern,
+    /// e.g. "Noto Sans:weight=light".
     #[arg(long)]
     main_font: Option<String>,
-    /// Largely used as a fallback font for unicode symbols. May be provided multiple times.
+    /// Largely used as a fallback font for unicode symbols, given like the main font. May be
+    /// provided multiple times.
     #[arg(long, value_parser)]
     secondary_font: Vec<String>,
+    /// How modules match their items, overriding the config file: fuzzy, prefix, substring, exact
+    /// (whole words), regex, initials (of words) or extended (fzf's search syntax).
+    #[arg(long, value_parser = parse_match_mode)]
+    match_mode: Option<MatchMode>,
     /// Maximum number of results to be returned by each searcher
     #[arg(long)]
     queery_max: Option<u32>,
     #[arg(long)]
     icon_size: Option<f32>,
+    /// Icon theme to use, instead of the one set for gtk or kde
+    #[arg(long)]
+    icon_theme: Option<String>,
+    /// How matches are drawn, overriding the layout in the config file
+    #[arg(long, value_enum)]
+    layout: Option<Layout>,
+    /// Show as a wlr-layer-shell overlay on wayland compositors supporting it, instead of a
+    /// normal window.
+    #[cfg(feature = "layer-shell")]
+    #[arg(long)]
+    layer_shell: bool,
+    /// Run in the terminal instead of opening a window (e.g. over SSH).
+    #[arg(long)]
+    tui: bool,
+    /// With --tui, list the matches of all modules together ordered by score, rather than
+    /// grouped by module.
+    #[arg(long)]
+    merged: bool,
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+fn parse_format(format: &str) -> Result<OutputFormat, String> {
+    OutputFormat::from_rofi(format).ok_or(format!("unknown format {format:?}, expected s, i or d"))
+}
+
+fn parse_column(column: &str) -> Result<Column, String> {
+    Column::from_name(column).ok_or(format!(
+        "unknown column {column:?}, expected name, desc, icon or output"
+    ))
+}
+
+/// a single ascii char, or an escaped null byte, tab or newline
+fn parse_separator(separator: &str) -> Result<u8, String> {
+    match separator {
+        "\\0" => Ok(b'\0'),
+        "\\t" => Ok(b'\t'),
+        "\\n" => Ok(b'\n'),
+        _ => match separator.as_bytes() {
+            [byte] if byte.is_ascii() => Ok(*byte),
+            _ => Err(format!("expected a single ascii char, got {separator:?}")),
+        },
+    }
+}
+
+fn parse_match_mode(mode: &str) -> Result<MatchMode, String> {
+    MatchMode::from_name(mode).ok_or(format!("unknown match mode {mode:?}"))
+}
+
+/// Headless use of the active modules, without opening a window
+#[derive(Subcommand)]
+enum Command {
+    /// Print the ranked matches for some search text, one per line as tab separated module, id,
+    /// name, description, icon and score.
+    Query {
+        text: String,
+        /// Only search with this module (e.g. "applications")
+        #[arg(long)]
+        module: Option<String>,
+        /// Print a JSON array instead
+        #[arg(long)]
+        json: bool,
+    },
+    /// Act on a match by an id printed by query, as if it were selected in the dialogue. Prints
+    /// the completion instead, for matches that provide one (e.g. application categories).
+    Select { id: String },
 }
 
 // NOTE: only needs to be modified at the start, could unsafe mut a static? (without RefCell)
 thread_local! {
-    pub static STAY_OPEN: RefCell<bool> = Default::default();
     pub static ICONSIZE: RefCell<f32> = Default::default();
+    /// the exit status once the window is closed, that of cancelling unless a selection closed it
+    pub static EXIT_CODE: RefCell<i32> = const { RefCell::new(0) };
     // TODO: expose text size, primary, secondary, highlight, frame and background colors
 }
 
-#[derive(EframeMain)]
-#[eframe_main(init = "SearchThing::new(_cc)", options = "set_opts()")]
 struct SearchThing {
     search_input: String,
-    last_queery: String,
-    searchers: Vec<WrappedSearcher>,
-    icon_path_cache: AppIconPathCache,
-    keyboard_idx: usize,
+    session: Session,
+    message: Option<String>,
+    icon_cache: AppIconCache,
+    keybindings: Keybindings,
+    /// move the cursor of the search input to this char index on the next frame
+    cursor_to: Option<usize>,
+    font_settings: FontSettings,
+    show_settings: bool,
+    show_preview: bool,
+    window: WindowConfig,
+    /// the window size once the monitor size is known, the maximum with auto height
+    window_size: Option<Vec2>,
+    layout: Layout,
 }
 
 impl SearchThing {
-    fn new(cc: &CreationContext) -> Self {
-        let mut args = SearchThingArgs::parse();
+    fn new(ctx: &egui::Context, mut args: SearchThingArgs) -> Self {
+        let config = config();
 
-        egui_extras::install_image_loaders(&cc.egui_ctx);
-        cc.egui_ctx
-            .set_fonts(custom_egui_font_def(args.main_font, args.secondary_font));
+        egui_extras::install_image_loaders(ctx);
+        let font_settings = FontSettings::new(&args.main_font, &args.secondary_font);
+        let searchers = load_searchers(&args);
+        fonts::set_fonts(ctx, args.main_font, args.secondary_font);
 
-        STAY_OPEN.with_borrow_mut(|b| *b = args.stay_open);
-        ICONSIZE.with_borrow_mut(|b| *b = args.icon_size.unwrap_or(48.0));
-        let max_shown = args.queery_max.unwrap_or(10);
-        let mut searchers = vec![];
-        if let Some(prompt) = args.dmenu {
-            searchers.push(WrappedSearcher::new(DmenuModule::new(prompt), max_shown));
-            if args.init_search.is_none() {
-                args.init_search = Some(String::new());
-            }
-        } else if args.symbols {
-            searchers.push(WrappedSearcher::new(SymbolsModule::default(), max_shown));
-        } else if !args.no_builtin_modules {
-            searchers.push(WrappedSearcher::new(
-                ApplicationsModule::default(),
-                max_shown,
-            ));
-        }
-        for path in args.plugin {
-            let res = unsafe { PluginModule::new(&path) };
-            match res {
-                Ok(plug) => searchers.push(WrappedSearcher::new(plug, max_shown)),
-                Err(e) => warn!("Failed to load library {path:?}: {e}"),
-            }
+        let icon_size = args.icon_size.unwrap_or(48.0);
+        ICONSIZE.with_borrow_mut(|b| *b = icon_size);
+        if args.dmenu.is_some() && args.init_search.is_none() {
+            args.init_search = Some(String::new());
         }
         let search_input = match args.init_search {
             Some(si) => {
-                for searcher in &mut searchers {
-                    searcher.queery(&si);
-                }
                 // HACK: give the user a moment to release the enter key if calling from the command line,
                 // otherwise a selection is registered immediately
                 sleep(Duration::from_millis(50));
@@ -122,13 +253,78 @@ impl SearchThing {
             None => Default::default(),
         };
         Self {
-            last_queery: search_input.clone(),
+            session: {
+                let mut session = Session::new(searchers, &search_input, args.stay_open)
+                    .multi_select(args.multi_select);
+                session.set_cursor(args.selected_row.unwrap_or(0));
+                session
+            },
+            message: args.message,
             search_input,
-            searchers,
-            icon_path_cache: Default::default(),
-            keyboard_idx: 0,
+            icon_cache: AppIconCache::new(ctx, args.icon_theme, icon_size),
+            keybindings: keybindings(&args.bind, args.multi_select),
+            cursor_to: None,
+            font_settings,
+            show_settings: false,
+            show_preview: false,
+            window: config.window.clone(),
+            window_size: None,
+            layout: args.layout.unwrap_or(config.window.layout),
+        }
+    }
+}
+
+/// the modules enabled by the arguments, shared by the dialogue and headless commands
+fn load_searchers(args: &SearchThingArgs) -> Vec<WrappedSearcher> {
+    let max_shown = args.queery_max.unwrap_or(10);
+    let mut searchers = vec![];
+    if let Some(prompt) = &args.dmenu {
+        let options = DmenuOptions {
+            prompt: prompt.clone(),
+            format: args.format,
+            input_format: match (&args.delimiter, args.json_lines) {
+                (_, true) => InputFormat::Json,
+                (Some(delimiter), _) => InputFormat::Delimited {
+                    // NOTE: as the shell passes an escaped tab through as is
+                    delimiter: delimiter.replace("\\t", "\t"),
+                    columns: args.columns.clone(),
+                },
+                (None, false) => InputFormat::Lines,
+            },
+            allow_custom: !args.no_custom,
+            null_separated: args.print0,
+            separator: args.separator.unwrap_or(b'\n'),
+        };
+        let dmenu = WrappedSearcher::new(DmenuModule::new(options), max_shown);
+        searchers.push(dmenu.with_key("dmenu"));
+    } else if args.symbols {
+        searchers.push(WrappedSearcher::new(SymbolsModule::default(), max_shown));
+    } else if !args.no_builtin_modules {
+        searchers.push(WrappedSearcher::new(
+            ApplicationsModule::default(),
+            max_shown,
+        ));
+    }
+    for path in &args.plugin {
+        let res = unsafe { PluginModule::new(path) };
+        match res {
+            Ok(plug) => searchers.push(WrappedSearcher::new(plug, max_shown)),
+            Err(e) => warn!("Failed to load library {path:?}: {e}"),
+        }
+    }
+    for searcher in &mut searchers {
+        let configured = config().match_modes.get(searcher.key()).and_then(|name| {
+            let mode = MatchMode::from_name(name);
+            if mode.is_none() {
+                warn!("Unknown match mode {name:?} in config, ignoring it");
+            }
+            mode
+        });
+        if let Some(mode) = args.match_mode.or(configured) {
+            searcher.set_match_mode(mode);
         }
     }
+    searchers
 }
 
 static HIGHLIGHT_FRAME: FrameStyle = FrameStyle {
@@ -140,174 +336,417 @@ static HIGHLIGHT_FRAME: FrameStyle = FrameStyle {
 };
 
 impl SearchThing {
-    fn match_counts(&self) -> Vec<usize> {
-        self.searchers
-            .iter()
-            .map(|s| s.cached_matches().len())
-            .collect()
+    /// sizes and places the window once the monitor size is known
+    fn apply_geometry(&mut self, ctx: &egui::Context) {
+        if self.window_size.is_some() {
+            return;
+        }
+        let Some(monitor) = ctx.input(|i| i.viewport().monitor_size) else {
+            return;
+        };
+        let size = self.window.size(monitor);
+        let min_size = match self.window.auto_height {
+            true => vec2(size.x, 0.0),
+            false => size,
+        };
+        // NOTE: lifting the initial limits first, in case the window grows
+        ctx.send_viewport_cmd(ViewportCommand::MaxInnerSize(monitor));
+        ctx.send_viewport_cmd(ViewportCommand::MinInnerSize(min_size));
+        ctx.send_viewport_cmd(ViewportCommand::MaxInnerSize(size));
+        ctx.send_viewport_cmd(ViewportCommand::InnerSize(size));
+        if self.window.anchor != Anchor::Center {
+            let pointer = match self.window.anchor {
+                Anchor::Cursor => pointer_position().map(|p| p / ctx.pixels_per_point()),
+                _ => None,
+            };
+            let position = self.window.position(monitor, size, pointer);
+            ctx.send_viewport_cmd(ViewportCommand::OuterPosition(position));
+        }
+        self.window_size = Some(size);
+    }
+    /// draws the whole ui, for either backend
+    fn show(&mut self, ctx: &egui::Context) {
+        fonts::update_fonts(ctx);
+        egui::CentralPanel::default().show(ctx, |ui| self.inspect_mut("", ui));
+    }
+    /// closes the window (to exit with the given status), or shows the completion in the search
+    /// input
+    fn apply_outcome(&mut self, ctx: &egui::Context, outcome: Outcome, exit_code: i32) {
+        match outcome {
+            Outcome::Close => {
+                EXIT_CODE.with_borrow_mut(|c| *c = exit_code);
+                ctx.send_viewport_cmd(ViewportCommand::Close);
+            }
+            Outcome::Completed(text) => {
+                self.search_input = text;
+                self.cursor_to = Some(self.search_input.chars().count());
+            }
+            Outcome::StayOpen | Outcome::Nothing => {}
+        }
     }
 }
 
-fn kbd_idx(match_counts: &[usize], i: usize, j: usize) -> usize {
-    let count: usize = match_counts.iter().cloned().take(j).sum();
-    count + i
+/// the name and description of a match, with the better matching one highlighted, as found by
+/// [best_match]
+fn match_labels(
+    ui: &mut egui::Ui,
+    match_name: &str,
+    desc: &str,
+    mtch: &Option<(bool, i64, Vec<usize>)>,
+) {
+    match mtch {
+        Some((is_name_mtch, _, idxs)) => {
+            if *is_name_mtch {
+                ui.label(non_contiguous_highlight(
+                    match_name,
+                    idxs,
+                    Color32::GREEN,
+                    Color32::WHITE,
+                ));
+                ui.label(desc);
+            } else {
+                ui.label(RichText::new(match_name).color(Color32::WHITE));
+                ui.label(non_contiguous_highlight(
+                    desc,
+                    idxs,
+                    Color32::GREEN,
+                    Color32::GRAY,
+                ));
+            }
+        }
+        None => {
+            ui.label(RichText::new(match_name).color(Color32::WHITE));
+            ui.label(desc);
+        }
+    }
 }
 
-fn is_highlighted_match(match_counts: &[usize], i: usize, j: usize, k: usize) -> bool {
-    kbd_idx(match_counts, i, j) == k
+fn draw_match(
+    ui: &mut egui::Ui,
+    frame: egui::Frame,
+    icon: Icon,
+    match_name: &str,
+    desc: &str,
+    mtch: Option<(bool, i64, Vec<usize>)>,
+    layout: Layout,
+) -> egui::Response {
+    let resp = frame
+        .show(ui, |ui| match layout {
+            Layout::List => {
+                ui.separator(); // horizontal line expands frame to fill outer
+                ui.horizontal(|ui| {
+                    icon.inspect("", ui);
+                    ui.vertical(|ui| match_labels(ui, match_name, desc, &mtch));
+                });
+                ui.separator();
+            }
+            Layout::Compact => {
+                ui.horizontal(|ui| {
+                    icon.inspect("", ui);
+                    match_labels(ui, match_name, desc, &mtch);
+                    // expands frame to fill outer
+                    ui.allocate_space(vec2(ui.available_width(), 0.0));
+                });
+            }
+            Layout::Grid => {
+                let is = ICONSIZE.with_borrow(|is| *is);
+                ui.set_width(2.0 * is);
+                ui.vertical_centered(|ui| {
+                    icon.inspect("", ui);
+                    ui.add(egui::Label::new(RichText::new(match_name).small()).truncate());
+                });
+            }
+        })
+        .response;
+    match layout {
+        Layout::Grid if !desc.is_empty() => resp.on_hover_text(format!("{match_name}\n{desc}")),
+        _ => resp,
+    }
 }
 
 impl EguiInspect for SearchThing {
     fn inspect_mut(&mut self, _label: &str, ui: &mut egui::Ui) {
-        let resp =
-            ui.add(egui::TextEdit::singleline(&mut self.search_input).desired_width(f32::INFINITY));
-        resp.request_focus();
-        if resp.changed() && !self.search_input.is_empty() {
-            for searcher in &mut self.searchers {
-                searcher.queery(&self.search_input);
+        self.icon_cache.receive();
+        self.apply_geometry(ui.ctx());
+        // NOTE: polls take in a bounded number of options, any left over are picked up right away
+        if self.session.poll_updates() && self.session.loading() {
+            ui.ctx().request_repaint();
+        } else if self.session.loading() {
+            ui.ctx().request_repaint_after(Duration::from_millis(100));
+        }
+
+        if ui.input(|i| i.key_pressed(Key::F2)) {
+            self.show_settings = !self.show_settings;
+        }
+        if self.show_settings {
+            self.font_settings.show(ui);
+            if ui.input(|i| i.key_released(Key::Escape)) {
+                self.show_settings = false;
             }
-            self.last_queery = self.search_input.clone();
-        };
+            return;
+        }
 
-        let match_counts = self.match_counts();
-        let total_matches = match_counts.iter().sum::<usize>();
+        // NOTE: taken before the search input gets to handle them (e.g. Tab, Home and End)
+        let commands = ui.input_mut(|i| self.keybindings.consume_commands(i));
+        let input_id = egui::Id::new("search_input");
+        for command in commands.iter().filter(|c| c.is_line_edit()) {
+            let cursor = self
+                .cursor_to
+                .or_else(|| {
+                    let state = TextEditState::load(ui.ctx(), input_id)?;
+                    Some(state.cursor.char_range()?.primary.index)
+                })
+                .unwrap_or(self.search_input.chars().count());
+            self.cursor_to = Some(edit_line(*command, &mut self.search_input, cursor));
+        }
+        if let Some(cursor) = self.cursor_to.take() {
+            let mut state = TextEditState::load(ui.ctx(), input_id).unwrap_or_default();
+            let cursor = CCursor::new(cursor);
+            state.cursor.set_char_range(Some(CCursorRange::one(cursor)));
+            state.store(ui.ctx(), input_id);
+        }
+        let resp = ui.add(
+            egui::TextEdit::singleline(&mut self.search_input)
+                .id(input_id)
+                .desired_width(f32::INFINITY),
+        );
+        resp.request_focus();
+        self.session.set_queery(&self.search_input);
+        if let Some(message) = &self.message {
+            ui.label(message);
+        }
+        if self.session.mark_count() > 0 {
+            ui.label(format!("{} marked", self.session.mark_count()));
+        }
 
         let mut mouse_activated = false;
         let mut mouse_moved = false;
-        let mut kbd_activated = false;
         let mut kbd_moved = false;
         let mut scrolling = false;
-        let mut requested_exit = false;
         ui.input(|i| {
             mouse_activated = i.pointer.button_clicked(egui::PointerButton::Primary);
             mouse_moved = i.pointer.time_since_last_movement() < 0.01; // TODO: a less arbitrary
                                                                        // threshhold?
             scrolling = i.pointer.middle_down();
-            kbd_activated = i.key_released(Key::Enter);
-            requested_exit = i.key_released(Key::Escape);
-            if i.key_released(Key::ArrowUp) && self.keyboard_idx > 0 {
-                self.keyboard_idx -= 1;
-                kbd_moved = true;
-            } else if i.key_released(Key::ArrowDown) && self.keyboard_idx < total_matches - 1
-            // TODO: support holding after delay, but do not trigger more than once per frame...
-            {
-                self.keyboard_idx += 1;
-                kbd_moved = true;
-            }
         });
-        if requested_exit {
-            exit(0);
+        let mut kbd_selection = None;
+        for command in commands {
+            match command {
+                KeyCommand::Exit => exit(EXIT_CODE.with_borrow(|c| *c)),
+                KeyCommand::TogglePreview => self.show_preview = !self.show_preview,
+                KeyCommand::AcceptCustom => kbd_selection = Some((Selection::Primary, command)),
+                command => {
+                    kbd_moved |= !command.is_line_edit();
+                    if let Some(selection) = self.session.command(command) {
+                        kbd_selection = Some((selection, command));
+                    }
+                }
+            }
+        }
+        if let Some((selection, command)) = kbd_selection {
+            let outcome = match command {
+                KeyCommand::AcceptCustom => self.session.select_custom(selection),
+                _ => self.session.select(selection),
+            };
+            self.apply_outcome(ui.ctx(), outcome, command.exit_code());
+        }
+
+        if self.show_preview {
+            let preview = self.session.preview();
+            egui::SidePanel::right("preview")
+                .resizable(false)
+                .exact_width(ui.available_width() / 3.0)
+                .show_inside(ui, |ui| {
+                    ui.label(preview.as_deref().unwrap_or("no preview"));
+                });
         }
 
-        let max_height = ui.available_height() / (self.searchers.len() as f32);
-        for (j, searcher) in self.searchers.iter_mut().enumerate() {
-            searcher.inspect_with_match_render(
+        let max_height = match (self.window.auto_height, self.window_size) {
+            // leaving space for the panel margin below
+            (true, Some(size)) => size.y - 2.0 * ui.cursor().top(),
+            _ => ui.available_height(),
+        } / (self.session.searchers().len() as f32);
+        let layout = self.layout;
+        let mut hovered = None;
+        let mut mouse_selection = None;
+        for (j, searcher) in self.session.searchers().iter().enumerate() {
+            let match_mode = searcher.match_mode().unwrap_or_default();
+            let selected = searcher.inspect_with_match_render(
                 ui,
                 |ui, icon_name, match_name, desc, i| {
-                    let is_highlighted =
-                        is_highlighted_match(&match_counts, i, j, self.keyboard_idx);
+                    let is_highlighted = self.session.is_highlighted(j, i);
                     let fs = match is_highlighted {
                         true => &HIGHLIGHT_FRAME,
                         false => &DEFAULT_FRAME_STYLE,
                     };
 
-                    let resp = fs
-                        .to_frame()
-                        .show(ui, |ui| {
-                            ui.separator(); // horizontal line expands frame to fill outer
-                            ui.horizontal(|ui| {
-                                self.icon_path_cache.get(&icon_name.into()).inspect("", ui);
-                                ui.vertical(|ui| {
-                                    let name_mtch = FuzzySearch::match_idxs(
-                                        &match_name.to_lowercase(),
-                                        &self.last_queery,
-                                    );
-                                    let desc_mtch = FuzzySearch::match_idxs(
-                                        &desc.to_lowercase(),
-                                        &self.last_queery,
-                                    );
-                                    let mtype = match (name_mtch, desc_mtch) {
-                                        (Some((ns, nidxs)), Some((ds, didxs))) => match ns > ds {
-                                            true => Some((true, nidxs)),
-                                            false => Some((false, didxs)),
-                                        },
-                                        (Some((_, idxs)), None) => Some((true, idxs)),
-                                        (None, Some((_, idxs))) => Some((false, idxs)),
-                                        (None, None) => None,
-                                    };
-                                    match mtype {
-                                        Some((is_name_mtch, idxs)) => {
-                                            if is_name_mtch {
-                                                ui.label(non_contiguous_highlight(
-                                                    match_name,
-                                                    &idxs,
-                                                    Color32::GREEN,
-                                                    Color32::WHITE,
-                                                ));
-                                                ui.label(desc);
-                                            } else {
-                                                ui.label(
-                                                    RichText::new(match_name).color(Color32::WHITE),
-                                                );
-                                                ui.label(non_contiguous_highlight(
-                                                    desc,
-                                                    &idxs,
-                                                    Color32::GREEN,
-                                                    Color32::GRAY,
-                                                ));
-                                            }
-                                        }
-                                        None => {
-                                            ui.label(
-                                                RichText::new(match_name).color(Color32::WHITE),
-                                            );
-                                            ui.label(desc);
-                                        }
-                                    }
-                                });
-                            });
-                            ui.separator();
-                        })
-                        .response;
+                    let icon = self.icon_cache.get(icon_name);
+                    let queery = self.session.queery();
+                    let mtch = best_match(match_name, desc, queery, match_mode);
+                    // marked matches are filled in, in multi-select mode
+                    let frame = match self.session.is_marked(j, i) {
+                        true => fs.to_frame().fill(ui.visuals().selection.bg_fill),
+                        false => fs.to_frame(),
+                    };
+                    let resp = draw_match(ui, frame, icon, match_name, desc, mtch, layout);
                     let mouse_highlighted = resp.contains_pointer();
 
                     if mouse_highlighted {
                         if mouse_moved {
-                            self.keyboard_idx = kbd_idx(&match_counts, i, j);
+                            hovered = Some((j, i));
                         }
-                        mouse_activated
-                    } else if is_highlighted {
-                        if kbd_moved {
+                        mouse_activated.then_some(Selection::Primary)
+                    } else {
+                        if is_highlighted && kbd_moved {
                             resp.scroll_to_me(None);
                         }
-                        kbd_activated
-                    } else {
-                        false
+                        None
                     }
                 },
                 max_height,
+                layout,
             );
+            mouse_selection = mouse_selection.or(selected.map(|(i, s)| (j, i, s)));
+        }
+        if let Some(row) = hovered.and_then(|(j, i)| self.session.row_of(j, i)) {
+            self.session.set_cursor(row);
         }
+        if let Some((j, i, selection)) = mouse_selection {
+            let outcome = self.session.select_match(j, i, selection);
+            self.apply_outcome(ui.ctx(), outcome, 0);
+        }
+        if let (true, Some(size)) = (self.window.auto_height, self.window_size) {
+            // NOTE: the preview panel takes up the full height, so this only shrinks without it
+            let used = ui.min_rect();
+            let height = (used.bottom() + used.top()).min(size.y).ceil();
+            let current = ui.ctx().input(|i| i.viewport().inner_rect);
+            if current.is_some_and(|r| (r.height() - height).abs() > 1.0) {
+                ui.ctx()
+                    .send_viewport_cmd(ViewportCommand::InnerSize(vec2(size.x, height)));
+            }
+        }
+    }
+}
 
-        self.icon_path_cache.batch_find(5);
+impl eframe::App for SearchThing {
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        self.show(ctx);
+    }
+}
+
+fn main() -> eframe::Result<()> {
+    let argv: Vec<_> = std::env::args_os()
+        .map(|arg| arg.to_string_lossy().into_owned())
+        .collect();
+    let mut args = match dmenu_compat::compat_args(&argv) {
+        Some(compat_argv) => SearchThingArgs::parse_from(compat_argv),
+        None => SearchThingArgs::parse(),
+    };
+    // NOTE: cancelling is only an error in dmenu mode, so that scripts can tell it apart
+    let cancel_code = match args.dmenu {
+        Some(_) => 1,
+        None => 0,
+    };
+    EXIT_CODE.with_borrow_mut(|c| *c = cancel_code);
+    if let Some(command) = args.command.take() {
+        // NOTE: no logger, as terminal logging would be mixed into the printed results
+        let searchers = load_searchers(&args);
+        exit(match cli::run(command, searchers) {
+            Ok(()) => 0,
+            Err(why) => {
+                eprintln!("{why}");
+                1
+            }
+        });
+    }
+    if args.tui {
+        // NOTE: no logger here either, as it would draw over the interface
+        let searchers = load_searchers(&args);
+        let init_search = args.init_search.unwrap_or_default();
+        let mut session = Session::new(searchers, &init_search, args.stay_open)
+            .merged(args.merged)
+            .multi_select(args.multi_select);
+        session.set_cursor(args.selected_row.unwrap_or(0));
+        let tui = tui::Tui::new(
+            session,
+            keybindings(&args.bind, args.multi_select),
+            args.message,
+            cancel_code,
+        );
+        match tui.run() {
+            Ok(code) => exit(code),
+            Err(why) => {
+                eprintln!("Terminal frontend failed: {why}");
+                exit(1);
+            }
+        }
+    }
+    let options = set_opts();
+    #[cfg(feature = "layer-shell")]
+    if args.layer_shell {
+        match layer_shell::LayerShellBackend::connect() {
+            Ok(backend) => {
+                if let Err(why) = backend.run(&config().window, |ctx| SearchThing::new(ctx, args)) {
+                    egui_inspect::logging::log::error!("Layer shell backend failed: {why}");
+                    exit(1);
+                }
+                exit(EXIT_CODE.with_borrow(|c| *c));
+            }
+            Err(why) => warn!("No layer shell ({why}), opening a normal window instead"),
+        }
+    }
+    eframe::run_native(
+        "SearchThing",
+        options,
+        Box::new(|cc| Ok(Box::new(SearchThing::new(&cc.egui_ctx, args)))),
+    )?;
+    exit(EXIT_CODE.with_borrow(|c| *c));
+}
+
+/// the config file keybindings, with those given by --bind over them
+fn keybindings(binds: &[String], multi_select: bool) -> Keybindings {
+    let mut table: HashMap<_, _> = match multi_select {
+        true => MULTI_SELECT_BINDINGS
+            .iter()
+            .map(|(c, n)| (c.to_string(), n.to_string()))
+            .collect(),
+        false => HashMap::new(),
+    };
+    table.extend(config().keybindings.clone());
+    for bind in binds {
+        match bind.rsplit_once('=') {
+            Some((chord, command)) => {
+                table.insert(chord.into(), command.into());
+            }
+            None => warn!("Expected a binding like Alt+1=custom-1, got {bind:?}"),
+        }
     }
+    Keybindings::new(&table)
 }
 
 fn set_opts() -> NativeOptions {
     setup_mixed_logger(FileLogOption::DefaultTempDir {
         log_name: "searchthing".into(),
     });
-    let window_builder: Option<WindowBuilderHook> = Some(Box::new(|mut vb| {
+    // NOTE: sizes relative to the monitor are applied once it is known, on the first frame
+    let window = &config().window;
+    let size = window.initial_size();
+    let min_size = match window.auto_height {
+        true => vec2(size.x, 0.0),
+        false => size,
+    };
+    let window_builder: Option<WindowBuilderHook> = Some(Box::new(move |mut vb| {
         // NOTE: sadly does not currently seem to work...
         vb.window_level = Some(egui::WindowLevel::AlwaysOnTop);
         vb.window_type = Some(egui::X11WindowType::Dialog);
-        vb.min_inner_size = Some(Vec2::new(800.0, 400.0));
-        vb.max_inner_size = Some(Vec2::new(800.0, 400.0));
+        vb.inner_size = Some(size);
+        vb.min_inner_size = Some(min_size);
+        vb.max_inner_size = Some(size);
         vb
     }));
     NativeOptions {
         window_builder,
-        centered: true,
+        centered: window.anchor == Anchor::Center,
         ..Default::default()
     }
 }
@@ -0,0 +1,395 @@
+//! A terminal frontend (`--tui`), for use over SSH or without a display. Drawn on the controlling
+//! terminal, so that stdin and stdout stay free for e.g. dmenu mode.
+
+use crate::keyboard::Keybindings;
+use egui_inspect::egui::{Key, Modifiers};
+use ratatui::{
+    crossterm::{
+        event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
+        execute,
+        terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+    },
+    layout::{Alignment, Constraint, Layout, Position},
+    prelude::CrosstermBackend,
+    style::{Color, Modifier, Style, Stylize},
+    text::{Line, Span},
+    widgets::{Block, List, ListItem, ListState, Paragraph, Wrap},
+    Frame, Terminal,
+};
+use searchthing_core::{best_match, edit_line, KeyCommand, Outcome, Selection, Session};
+use searchthing_interface::{char_from_codepoint, MatchInfo, MatchMode};
+use std::{
+    fs::{File, OpenOptions},
+    io,
+    time::Duration,
+};
+
+type TtyTerminal = Terminal<CrosstermBackend<File>>;
+
+pub struct Tui {
+    session: Session,
+    input: String,
+    /// in chars
+    cursor: usize,
+    keybindings: Keybindings,
+    /// shown beneath the search input
+    message: Option<String>,
+    show_preview: bool,
+    /// the exit status when cancelled
+    cancel_code: i32,
+}
+
+impl Tui {
+    pub fn new(
+        session: Session,
+        keybindings: Keybindings,
+        message: Option<String>,
+        cancel_code: i32,
+    ) -> Self {
+        let input = session.queery().to_string();
+        Self {
+            session,
+            cursor: input.chars().count(),
+            input,
+            keybindings,
+            message,
+            show_preview: false,
+            cancel_code,
+        }
+    }
+
+    /// Runs until cancelled, or a selection is made (unless staying open), returns the exit
+    /// status. The terminal is restored while acting on a selection, so that e.g. dmenu output is
+    /// not drawn over.
+    pub fn run(mut self) -> io::Result<i32> {
+        loop {
+            let mut terminal = enter()?;
+            let res = self.event_loop(&mut terminal);
+            leave(terminal)?;
+            let Some((selection, command)) = res? else {
+                return Ok(self.cancel_code);
+            };
+            let outcome = match command {
+                KeyCommand::AcceptCustom => self.session.select_custom(selection),
+                _ => self.session.select(selection),
+            };
+            match outcome {
+                Outcome::Completed(completion) => {
+                    self.cursor = completion.chars().count();
+                    self.input = completion;
+                }
+                Outcome::StayOpen | Outcome::Nothing => {}
+                Outcome::Close => return Ok(command.exit_code()),
+            }
+        }
+    }
+
+    /// returns the selection to make and the command that made it, or none when cancelled
+    fn event_loop(
+        &mut self,
+        terminal: &mut TtyTerminal,
+    ) -> io::Result<Option<(Selection, KeyCommand)>> {
+        loop {
+            let updated = self.session.poll_updates();
+            self.session.set_queery(&self.input);
+            terminal.draw(|frame| self.draw(frame))?;
+
+            // NOTE: redrawn regularly while options are still coming in, straight away if a poll
+            // may have left some behind
+            let wait = Duration::from_millis(if updated { 0 } else { 100 });
+            if self.session.loading() && !event::poll(wait)? {
+                continue;
+            }
+            let Event::Key(key) = event::read()? else {
+                continue;
+            };
+            if key.kind == KeyEventKind::Release {
+                continue;
+            }
+            // NOTE: raw mode turns Ctrl+C into a key press rather than SIGINT, it always exits
+            let interrupt =
+                key.code == KeyCode::Char('c') && key.modifiers == KeyModifiers::CONTROL;
+            let command = match interrupt {
+                true => Some(KeyCommand::Exit),
+                false => egui_chord(key).and_then(|(m, k)| self.keybindings.command(m, k)),
+            };
+            match command {
+                None => self.edit_input(key),
+                Some(KeyCommand::Exit) => return Ok(None),
+                Some(KeyCommand::TogglePreview) => self.show_preview = !self.show_preview,
+                Some(KeyCommand::AcceptCustom) => {
+                    return Ok(Some((Selection::Primary, KeyCommand::AcceptCustom)));
+                }
+                Some(command) if command.is_line_edit() => {
+                    self.cursor = edit_line(command, &mut self.input, self.cursor);
+                }
+                Some(command) => {
+                    // NOTE: even with no matches, as the search text may be selected as is
+                    if let Some(selection) = self.session.command(command) {
+                        return Ok(Some((selection, command)));
+                    }
+                }
+            }
+        }
+    }
+
+    /// typing and cursor movement in the search input, for keys without a binding
+    fn edit_input(&mut self, key: KeyEvent) {
+        let byte_idx =
+            |text: &str, c: usize| text.char_indices().nth(c).map_or(text.len(), |(i, _)| i);
+        let len = self.input.chars().count();
+        match key.code {
+            KeyCode::Char(c)
+                if !key
+                    .modifiers
+                    .intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) =>
+            {
+                self.input.insert(byte_idx(&self.input, self.cursor), c);
+                self.cursor += 1;
+            }
+            KeyCode::Backspace if self.cursor > 0 => {
+                self.cursor -= 1;
+                self.input.remove(byte_idx(&self.input, self.cursor));
+            }
+            KeyCode::Delete if self.cursor < len => {
+                self.input.remove(byte_idx(&self.input, self.cursor));
+            }
+            KeyCode::Left => self.cursor = self.cursor.saturating_sub(1),
+            KeyCode::Right => self.cursor = (self.cursor + 1).min(len),
+            _ => {}
+        }
+    }
+
+    fn draw(&self, frame: &mut Frame) {
+        let message_height = self.message.as_ref().map_or(0, |m| m.lines().count()) as u16;
+        let [input_area, message_area, results_area] = Layout::vertical([
+            Constraint::Length(1),
+            Constraint::Length(message_height),
+            Constraint::Min(0),
+        ])
+        .areas(frame.area());
+        let before_cursor: String = self.input.chars().take(self.cursor).collect();
+        let prompt = Span::styled("> ", Style::new().fg(Color::Green));
+        let cursor_x = input_area.x + (prompt.width() + Span::raw(before_cursor).width()) as u16;
+        frame.render_widget(
+            Paragraph::new(Line::from(vec![prompt, Span::raw(&self.input)])),
+            input_area,
+        );
+        frame.set_cursor_position(Position::new(cursor_x, input_area.y));
+        let mut status: Vec<_> = self
+            .session
+            .searchers()
+            .iter()
+            .filter_map(|s| s.status())
+            .collect();
+        if self.session.mark_count() > 0 {
+            status.push(format!("{} marked", self.session.mark_count()));
+        }
+        frame.render_widget(
+            Paragraph::new(status.join("  ").yellow()).alignment(Alignment::Right),
+            input_area,
+        );
+        if let Some(message) = &self.message {
+            frame.render_widget(Paragraph::new(message.as_str()).italic(), message_area);
+        }
+
+        let results_area = match self.show_preview {
+            true => {
+                let [results_area, preview_area] =
+                    Layout::horizontal([Constraint::Ratio(2, 3), Constraint::Ratio(1, 3)])
+                        .areas(results_area);
+                let preview = self.session.preview();
+                frame.render_widget(
+                    Paragraph::new(preview.unwrap_or("no preview".into()))
+                        .wrap(Wrap { trim: false })
+                        .block(Block::bordered().title("preview")),
+                    preview_area,
+                );
+                results_area
+            }
+            false => results_area,
+        };
+
+        let merged = self.session.is_merged();
+        let rows = self.session.rows();
+        let mut items = vec![];
+        let mut selected = None;
+        let mut last_module = None;
+        for (row, (j, i)) in rows.iter().enumerate() {
+            let searcher = &self.session.searchers()[*j];
+            if !merged && last_module != Some(*j) {
+                items.push(ListItem::new(Line::from(searcher.name().bold())));
+                last_module = Some(*j);
+            }
+            if row == self.session.cursor() {
+                selected = Some(items.len());
+            }
+            let Some(info) = searcher.match_info(*i) else {
+                continue;
+            };
+            let module = merged.then(|| searcher.name());
+            let mode = searcher.match_mode().unwrap_or_default();
+            let mut line = match_line(info, self.session.queery(), mode, module);
+            if self.session.is_marked(*j, *i) {
+                line.spans
+                    .insert(0, Span::styled("✓ ", Style::new().yellow()));
+            }
+            items.push(ListItem::new(line));
+        }
+        if rows.is_empty() {
+            items.push(ListItem::new(Line::from("no matches".dark_gray())));
+        }
+        let list = List::new(items)
+            .highlight_style(Style::new().add_modifier(Modifier::REVERSED))
+            .highlight_symbol("▌");
+        let mut state = ListState::default().with_selected(selected);
+        frame.render_stateful_widget(list, results_area, &mut state);
+    }
+}
+
+/// a match on one line, with the matched chars of the better matching of name and description
+/// highlighted
+fn match_line<'a>(
+    info: MatchInfo<'a>,
+    queery: &str,
+    mode: MatchMode,
+    module: Option<&'a str>,
+) -> Line<'a> {
+    let MatchInfo { name, desc, icon } = info;
+    let (name_idxs, desc_idxs) = match best_match(name, desc, queery, mode) {
+        Some((true, _, idxs)) => (idxs, vec![]),
+        Some((false, _, idxs)) => (vec![], idxs),
+        None => (vec![], vec![]),
+    };
+    let mut spans = vec![];
+    if let Some(c) = icon
+        .starts_with("U+")
+        .then(|| char_from_codepoint(icon))
+        .flatten()
+    {
+        spans.push(Span::raw(format!("{c} ")));
+    }
+    spans.extend(highlight(name, &name_idxs, Style::new()));
+    if !desc.is_empty() {
+        spans.push(Span::raw("  "));
+        spans.extend(highlight(desc, &desc_idxs, Style::new().fg(Color::Gray)));
+    }
+    if let Some(module) = module {
+        spans.push(Span::styled(
+            format!("  [{module}]"),
+            Style::new().dark_gray(),
+        ));
+    }
+    Line::from(spans)
+}
+
+fn highlight<'a>(text: &'a str, idxs: &[usize], style: Style) -> Vec<Span<'a>> {
+    let highlighted = Style::new().fg(Color::Green).add_modifier(Modifier::BOLD);
+    text.char_indices()
+        .enumerate()
+        .map(|(c, (i, ch))| {
+            let style = match idxs.contains(&c) {
+                true => highlighted,
+                false => style,
+            };
+            Span::styled(&text[i..i + ch.len_utf8()], style)
+        })
+        .collect()
+}
+
+/// the chord in the terms of the keybindings, which are shared with the egui frontend
+fn egui_chord(key: KeyEvent) -> Option<(Modifiers, Key)> {
+    let mut modifiers = Modifiers::NONE;
+    if key.modifiers.contains(KeyModifiers::CONTROL) {
+        modifiers |= Modifiers::CTRL;
+    }
+    if key.modifiers.contains(KeyModifiers::SHIFT) {
+        modifiers |= Modifiers::SHIFT;
+    }
+    if key.modifiers.contains(KeyModifiers::ALT) {
+        modifiers |= Modifiers::ALT;
+    }
+    let key = match key.code {
+        KeyCode::Up => Key::ArrowUp,
+        KeyCode::Down => Key::ArrowDown,
+        KeyCode::Left => Key::ArrowLeft,
+        KeyCode::Right => Key::ArrowRight,
+        KeyCode::PageUp => Key::PageUp,
+        KeyCode::PageDown => Key::PageDown,
+        KeyCode::Home => Key::Home,
+        KeyCode::End => Key::End,
+        KeyCode::Tab => Key::Tab,
+        KeyCode::BackTab => {
+            modifiers |= Modifiers::SHIFT;
+            Key::Tab
+        }
+        KeyCode::Enter => Key::Enter,
+        KeyCode::Esc => Key::Escape,
+        KeyCode::Backspace => Key::Backspace,
+        KeyCode::Delete => Key::Delete,
+        KeyCode::Insert => Key::Insert,
+        KeyCode::F(n) => Key::from_name(&format!("F{n}"))?,
+        KeyCode::Char(c) => Key::from_name(&c.to_string())?,
+        _ => return None,
+    };
+    Some((modifiers, key))
+}
+
+#[test]
+fn egui_chord_test() {
+    let chord = |code, modifiers| egui_chord(KeyEvent::new(code, modifiers));
+    assert_eq!(
+        chord(KeyCode::Char('p'), KeyModifiers::CONTROL),
+        Some((Modifiers::CTRL, Key::P))
+    );
+    assert_eq!(
+        chord(KeyCode::BackTab, KeyModifiers::SHIFT),
+        Some((Modifiers::SHIFT, Key::Tab))
+    );
+    assert_eq!(
+        chord(KeyCode::F(3), KeyModifiers::NONE),
+        Some((Modifiers::NONE, Key::F3))
+    );
+    assert_eq!(chord(KeyCode::Char('é'), KeyModifiers::NONE), None);
+    // as reported without keyboard enhancement, Ctrl+Space being a null byte and the Shift of
+    // Shift+Enter lost
+    assert_eq!(
+        chord(KeyCode::Char(' '), KeyModifiers::CONTROL),
+        Some((Modifiers::CTRL, Key::Space))
+    );
+    assert_eq!(
+        chord(KeyCode::Enter, KeyModifiers::NONE),
+        Some((Modifiers::NONE, Key::Enter))
+    );
+    let multi_select = crate::keyboard::MULTI_SELECT_BINDINGS
+        .iter()
+        .map(|(c, n)| (c.to_string(), n.to_string()))
+        .collect();
+    let keybindings = Keybindings::new(&multi_select);
+    let command = |code, modifiers| {
+        let (m, k) = chord(code, modifiers)?;
+        keybindings.command(m, k)
+    };
+    assert_eq!(
+        command(KeyCode::Char(' '), KeyModifiers::CONTROL),
+        Some(KeyCommand::ToggleMark)
+    );
+    assert_eq!(
+        command(KeyCode::Enter, KeyModifiers::NONE),
+        Some(KeyCommand::Select)
+    );
+}
+
+fn enter() -> io::Result<TtyTerminal> {
+    // NOTE: crossterm reads keys from /dev/tty when stdin is not a terminal
+    let mut tty = OpenOptions::new().read(true).write(true).open("/dev/tty")?;
+    enable_raw_mode()?;
+    execute!(tty, EnterAlternateScreen)?;
+    Terminal::new(CrosstermBackend::new(tty))
+}
+
+fn leave(mut terminal: TtyTerminal) -> io::Result<()> {
+    disable_raw_mode()?;
+    execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
+    terminal.show_cursor()
+}
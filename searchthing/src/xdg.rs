@@ -0,0 +1,51 @@
+//! XDG base directory helpers.
+
+use egui_inspect::logging::log::{info, warn};
+use serde::{de::DeserializeOwned, Serialize};
+use std::{
+    env, fs,
+    io::{BufReader, BufWriter},
+    path::{Path, PathBuf},
+};
+
+pub fn home_dir() -> PathBuf {
+    PathBuf::from(env::var("HOME").unwrap_or_default())
+}
+
+/// the dir in an XDG environment variable, or its default relative to home
+pub fn xdg_dir(var: &str, home_fallback: &str) -> PathBuf {
+    env::var(var)
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| home_dir().join(home_fallback))
+}
+
+/// where searchthing keeps its caches, e.g. the icon index
+pub fn cache_dir() -> PathBuf {
+    xdg_dir("XDG_CACHE_HOME", ".cache").join("searchthing")
+}
+
+/// Reads what is cached as JSON at the path if it is still current (e.g. of the same version and
+/// source mtimes), otherwise builds it and writes it there, `what` naming it in the log.
+pub fn load_cache<T: Serialize + DeserializeOwned>(
+    path: &Path,
+    what: &str,
+    is_current: impl FnOnce(&T) -> bool,
+    build: impl FnOnce() -> T,
+) -> T {
+    let cached: Option<T> = fs::File::open(path)
+        .ok()
+        .and_then(|f| serde_json::from_reader(BufReader::new(f)).ok());
+    if let Some(cached) = cached.filter(is_current) {
+        return cached;
+    }
+    info!("Rebuilding {what} at {path:?}");
+    let built = build();
+    let res = fs::create_dir_all(path.parent().unwrap_or(Path::new("/")))
+        .and_then(|_| fs::File::create(path))
+        .map_err(|e| e.to_string())
+        .and_then(|f| serde_json::to_writer(BufWriter::new(f), &built).map_err(|e| e.to_string()));
+    if let Err(why) = res {
+        warn!("Failed to write {what} to {path:?}: {why}");
+    }
+    built
+}
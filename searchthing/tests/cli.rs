@@ -0,0 +1,47 @@
+use std::{
+    io::Write,
+    process::{Command, Stdio},
+};
+
+/// runs the dmenu module headless, with the given lines on stdin
+fn dmenu(lines: &str, args: &[&str]) -> (bool, String) {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_searchthing"))
+        .args(["--dmenu=Pick"])
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(lines.as_bytes())
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+    (
+        output.status.success(),
+        String::from_utf8(output.stdout).unwrap(),
+    )
+}
+
+#[test]
+fn query_and_select_test() {
+    let lines = "firefox\nfoot\nfish\n";
+    let (ok, out) = dmenu(lines, &["query", "fo"]);
+    assert!(ok);
+    let ids: Vec<_> = out.lines().map(|l| l.split('\t').nth(1).unwrap()).collect();
+    assert_eq!(ids, ["dmenu:1", "dmenu:0"]);
+
+    let (ok, out) = dmenu(lines, &["query", "fish", "--json"]);
+    assert!(ok);
+    let results: serde_json::Value = serde_json::from_str(&out).unwrap();
+    assert_eq!(results[0]["name"], "fish");
+
+    assert_eq!(
+        dmenu(lines, &["select", "dmenu:1"]),
+        (true, "foot\n".into())
+    );
+    assert!(!dmenu(lines, &["select", "nope:1"]).0);
+    assert_eq!(dmenu(lines, &["select", "dmenu:7"]), (false, "".into()));
+}